@@ -1,22 +1,23 @@
 pub(crate) mod data_types {
-    use std::{fmt, io::Cursor, str};
-    use chrono::{TimeZone, Utc, LocalResult};
+    use std::{collections::HashSet, fmt, fs, io::{Cursor, Write}, path::{Path, PathBuf}, str, time::{Duration, SystemTime}};
+    use chrono::{Datelike, Days, Local, LocalResult, Months, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc, Weekday};
     use serde::{Serialize, Deserialize};
-    use reqwest::{Client, Response, header::HeaderValue};
-    use prettytable::{Table, Row};
+    use reqwest::{Client, RequestBuilder, Response, header::HeaderValue};
+    use prettytable::{Table, Row, Cell};
     use quick_xml::{de::from_str, Reader, events::{attributes::Attribute, Event, BytesStart, BytesText, BytesEnd}, Writer};
     use rand::Rng;
+    use crate::error::{AppError, AppResult};
     //use http::uri;
 
     #[derive(Serialize, Deserialize)]
     struct Registry {
-        #[serde(rename = "entry")]
+        #[serde(rename = "entry", default)]
         entries: Vec<AppElement>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     struct AppElementTags {
-        #[serde(rename = "tag")]
+        #[serde(rename = "tag", default)]
         tags: Vec<String>,
     }
 
@@ -40,52 +41,542 @@ pub(crate) mod data_types {
     pub struct AppElement {
         #[serde(rename = "@id")]
         id: Option<u16>,
+        /// Identifies the element across clients, even when two of them picked
+        /// the same ID while offline. Missing in registries of older clients
+        #[serde(rename = "@uuid", default, skip_serializing_if = "Option::is_none")]
+        uuid: Option<String>,
         #[serde(rename = "name")]
         title: String,
         description: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
         due: Option<u32>,
+        /// Unix timestamp of when the event starts, None for tasks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start: Option<u32>,
+        /// Unix timestamp of when the event ends, the start of the last day
+        /// for all-day events. None for events without a known end
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end: Option<u32>,
+        /// Whether the event takes whole days, its times are not shown then
+        #[serde(rename = "allday", default, skip_serializing_if = "std::ops::Not::not")]
+        all_day: bool,
+        /// Unix timestamp of when the element was completed, None while it is open
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        completed: Option<u32>,
+        #[serde(default, skip_serializing_if = "Priority::is_none")]
+        priority: Priority,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        recurrence: Option<Recurrence>,
+        /// ID of the element this one is a subtask of
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent: Option<u16>,
         tags: Option<AppElementTags>,
         #[serde(skip)]
         removed: bool,
         #[serde(skip)]
         modified: bool,
+        /// Identifies the element within the local state, also before it got
+        /// an ID. Is 0 until the element is added to a state
+        #[serde(skip)]
+        handle: u32,
+        /// Handle of the parent element within the local state, also before
+        /// the parent got an ID. Is 0 for elements without a known parent
+        #[serde(skip)]
+        parent_handle: u32,
     }
 
-    impl PartialEq for AppElement {
-        fn eq(&self, other: &AppElement) -> bool {
-            match self.id {
-                Some(id) => Some(id) == other.id,
-                None => self == other, // Isn't this recursive???
+    /// How important an element is, elements without a priority come last
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+    pub enum Priority {
+        #[default]
+        None,
+        Low,
+        Medium,
+        High,
+    }
+
+    impl Priority {
+        pub const ALL: [Priority; 4] = [Self::None, Self::Low, Self::Medium, Self::High];
+
+        fn is_none(&self) -> bool {
+            *self == Self::None
+        }
+
+        /// The name used in the registry and on the command line
+        fn name(&self) -> &'static str {
+            match self {
+                Self::None => "none",
+                Self::Low => "low",
+                Self::Medium => "medium",
+                Self::High => "high",
+            }
+        }
+
+        /// The table style that highlights the priority
+        fn style(&self) -> &'static str {
+            match self {
+                Self::None | Self::Low => "",
+                Self::Medium => "Fy",
+                Self::High => "Frb",
             }
         }
     }
 
-    impl fmt::Display for AppElement {
+    impl fmt::Display for Priority {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let disp_due: String = match self.due {
-                Some(due) => {
-                    let due_timestamp: i64 = due.into();
-                    let utc_due: String = match Utc.timestamp_opt(due_timestamp, 0) {
-                        LocalResult::None => "None".to_string(),
-                        LocalResult::Single(val) => val.with_timezone(&chrono::Local).to_rfc2822(),
-                        LocalResult::Ambiguous(val, _) => val.with_timezone(&chrono::Local).to_rfc2822(),
+            let displ: &str = match self {
+                Self::None => "None",
+                Self::Low => "Low",
+                Self::Medium => "Medium",
+                Self::High => "High",
+            };
+            write!(f, "{}", displ)
+        }
+    }
+
+    impl str::FromStr for Priority {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let s: String = s.trim().to_lowercase();
+            if s.is_empty() {
+                return Ok(Self::None);
+            }
+            Self::ALL
+                .into_iter()
+                .find(|e| e.name() == s)
+                .ok_or(format!("Unknown priority '{}', expected none, low, medium or high", s))
+        }
+    }
+
+    impl Serialize for Priority {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.name())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Priority {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// How often a recurring element repeats
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Frequency {
+        Daily,
+        Weekly,
+        Monthly,
+        Yearly,
+    }
+
+    impl Frequency {
+        const ALL: [Frequency; 4] = [Self::Daily, Self::Weekly, Self::Monthly, Self::Yearly];
+
+        /// The name used in recurrence rules
+        fn name(&self) -> &'static str {
+            match self {
+                Self::Daily => "DAILY",
+                Self::Weekly => "WEEKLY",
+                Self::Monthly => "MONTHLY",
+                Self::Yearly => "YEARLY",
+            }
+        }
+
+        fn unit(&self) -> &'static str {
+            match self {
+                Self::Daily => "day",
+                Self::Weekly => "week",
+                Self::Monthly => "month",
+                Self::Yearly => "year",
+            }
+        }
+    }
+
+    /// The weekdays with their codes in recurrence rules
+    const WEEKDAYS: [(Weekday, &str); 7] = [
+        (Weekday::Mon, "MO"),
+        (Weekday::Tue, "TU"),
+        (Weekday::Wed, "WE"),
+        (Weekday::Thu, "TH"),
+        (Weekday::Fri, "FR"),
+        (Weekday::Sat, "SA"),
+        (Weekday::Sun, "SU"),
+    ];
+
+    /// Format of the end date in recurrence rules
+    const UNTIL_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+    /// A rule after which an element repeats. Written like the RRULE of
+    /// iCalendar, e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;COUNT=5`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Recurrence {
+        freq: Frequency,
+        /// Repeats every n days, weeks, months or years
+        interval: u32,
+        /// The weekdays the element falls on, used by daily and weekly rules
+        by_day: Vec<Weekday>,
+        /// The day of the month the element falls on, used by monthly and yearly
+        /// rules. Months without that day take their last day instead
+        by_month_day: Option<u32>,
+        /// Unix timestamp after which there are no more occurrences
+        until: Option<u32>,
+        /// The number of occurrences left, including the current one
+        count: Option<u32>,
+    }
+
+    impl Recurrence {
+        /// Returns the rule in its written form
+        fn rule(&self) -> String {
+            let mut parts: Vec<String> = vec![format!("FREQ={}", self.freq.name())];
+            if self.interval != 1 {
+                parts.push(format!("INTERVAL={}", self.interval));
+            }
+            if !self.by_day.is_empty() {
+                parts.push(format!("BYDAY={}", self.day_codes().join(",")));
+            }
+            if let Some(day) = self.by_month_day {
+                parts.push(format!("BYMONTHDAY={}", day));
+            }
+            if let Some(LocalResult::Single(until)) = self.until.map(|e| Utc.timestamp_opt(e.into(), 0)) {
+                parts.push(format!("UNTIL={}", until.format(UNTIL_FORMAT)));
+            }
+            if let Some(count) = self.count {
+                parts.push(format!("COUNT={}", count));
+            }
+            parts.join(";")
+        }
+
+        fn day_codes(&self) -> Vec<&'static str> {
+            WEEKDAYS
+                .iter()
+                .filter(|e| self.by_day.contains(&e.0))
+                .map(|e| e.1)
+                .collect()
+        }
+
+        /// Returns the occurrence following the one due at the given time,
+        /// together with the rule that is left for it. None if the rule ends
+        fn next(&self, due: u32) -> Option<(u32, Recurrence)> {
+            self.next_in(due, &Local)
+        }
+
+        /// Returns the occurrence following the one due at the given time in the
+        /// given time zone. An occurrence in the gap of a change to daylight
+        /// saving time is moved to the same time after the gap
+        pub fn next_in<Tz: TimeZone>(&self, due: u32, tz: &Tz) -> Option<(u32, Recurrence)> {
+            if self.count.is_some_and(|e| e <= 1) {
+                return None;
+            }
+            let start: NaiveDateTime = tz.timestamp_opt(due.into(), 0).single()?.naive_local();
+            let interval: u32 = self.interval.max(1);
+            // Monthly rules keep to the day of the first occurrence, a shorter
+            // month in between must not move it
+            let month_day: u32 = self.by_month_day.unwrap_or(start.day());
+            let next: NaiveDateTime = match self.freq {
+                Frequency::Daily | Frequency::Weekly if !self.by_day.is_empty() => self.next_day(start)?,
+                Frequency::Daily => start + TimeDelta::days(interval.into()),
+                Frequency::Weekly => start + TimeDelta::weeks(interval.into()),
+                Frequency::Monthly => on_month_day(start, Months::new(interval), month_day)?,
+                Frequency::Yearly => on_month_day(start, Months::new(interval.checked_mul(12)?), month_day)?,
+            };
+            let next: u32 = tz
+                .from_local_datetime(&next)
+                .earliest()
+                .or_else(|| tz.from_local_datetime(&(next + TimeDelta::hours(1))).earliest())?
+                .timestamp()
+                .try_into()
+                .ok()?;
+            if self.until.is_some_and(|e| next > e) {
+                return None;
+            }
+            let mut rest: Recurrence = self.clone();
+            rest.count = self.count.map(|e| e - 1);
+            if matches!(self.freq, Frequency::Monthly | Frequency::Yearly) {
+                rest.by_month_day = Some(month_day);
+            }
+            Some((next, rest))
+        }
+
+        /// Returns the next day after the given one that is one of the weekdays
+        /// of the rule and in a day or week that matches the interval
+        fn next_day(&self, start: NaiveDateTime) -> Option<NaiveDateTime> {
+            let week_start = |e: NaiveDate| e - TimeDelta::days(e.weekday().num_days_from_monday().into());
+            let interval: i64 = self.interval.max(1).into();
+            (1..=8 * 7 * interval)
+                .map(|e| start + TimeDelta::days(e))
+                .find(|e| {
+                    let offset: i64 = match self.freq {
+                        Frequency::Weekly => (week_start(e.date()) - week_start(start.date())).num_weeks(),
+                        _ => (e.date() - start.date()).num_days(),
                     };
-                    utc_due
-                },
-                None => "None".to_string()
+                    self.by_day.contains(&e.weekday()) && offset % interval == 0
+                })
+        }
+
+        /// Returns the first occurrence after the given time, skipping the ones
+        /// that passed already, together with the rule that is left for it
+        fn next_after(&self, due: u32, after: u32) -> Option<(u32, Recurrence)> {
+            let (mut due, mut rest) = self.next(due)?;
+            while due <= after {
+                (due, rest) = rest.next(due)?;
+            }
+            Some((due, rest))
+        }
+    }
+
+    /// Returns the given day of the month that is the given number of months
+    /// after the start, or the last day of that month if it is shorter
+    fn on_month_day(start: NaiveDateTime, months: Months, day: u32) -> Option<NaiveDateTime> {
+        let first: NaiveDateTime = start.with_day(1)?.checked_add_months(months)?;
+        let last: u32 = (first.checked_add_months(Months::new(1))? - TimeDelta::days(1)).day();
+        first.with_day(day.min(last))
+    }
+
+    impl fmt::Display for Recurrence {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.interval {
+                1 => write!(f, "every {}", self.freq.unit())?,
+                n => write!(f, "every {} {}s", n, self.freq.unit())?,
+            }
+            if !self.by_day.is_empty() {
+                write!(f, " on {}", self.day_codes().join(", "))?;
+            }
+            if let Some(day) = self.by_month_day {
+                write!(f, " on day {}", day)?;
+            }
+            if self.until.is_some() {
+                write!(f, " until {}", format_due(self.until))?;
+            }
+            if let Some(count) = self.count {
+                write!(f, ", {} more time(s)", count)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl str::FromStr for Recurrence {
+        type Err = String;
+
+        /// Parses a written rule, a frequency alone like 'weekly' works as well
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let s: String = s.trim().to_uppercase();
+            let s: &str = s.strip_prefix("RRULE:").unwrap_or(&s);
+            let mut recurrence: Recurrence = Recurrence {
+                freq: Frequency::Daily,
+                interval: 1,
+                by_day: Vec::new(),
+                by_month_day: None,
+                until: None,
+                count: None,
+            };
+            let mut freq: Option<Frequency> = None;
+            let parts: Vec<(&str, &str)> = match s.contains('=') {
+                true => s.split(';').filter(|e| !e.is_empty()).map(|e| e.split_once('=').unwrap_or((e, ""))).collect(),
+                false => vec![("FREQ", s)],
             };
+            for (key, value) in parts {
+                let invalid = || format!("Invalid {} '{}' in the rule", key, value);
+                match key {
+                    "FREQ" => freq = Some(Frequency::ALL.into_iter().find(|e| e.name() == value).ok_or_else(invalid)?),
+                    "INTERVAL" => recurrence.interval = value.parse().ok().filter(|e| *e != 0).ok_or_else(invalid)?,
+                    "BYDAY" => for day in value.split(',') {
+                        recurrence.by_day.push(WEEKDAYS.iter().find(|e| e.1 == day).ok_or_else(invalid)?.0);
+                    },
+                    "BYMONTHDAY" => recurrence.by_month_day = Some(value.parse().ok().filter(|e| (1..=31).contains(e)).ok_or_else(invalid)?),
+                    "UNTIL" => {
+                        let until: NaiveDateTime = NaiveDateTime::parse_from_str(value, UNTIL_FORMAT)
+                            .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d").map(|e| e.and_hms_opt(23, 59, 59).unwrap_or_default()))
+                            .map_err(|_| invalid())?;
+                        recurrence.until = Some(until.and_utc().timestamp().try_into().map_err(|_| invalid())?);
+                    },
+                    "COUNT" => recurrence.count = Some(value.parse().ok().filter(|e| *e != 0).ok_or_else(invalid)?),
+                    _ => return Err(format!("Unknown part '{}' in the rule", key)),
+                }
+            }
+            recurrence.freq = freq.ok_or("The rule needs a FREQ of DAILY, WEEKLY, MONTHLY or YEARLY".to_string())?;
+            // Only the combinations the next occurrence is computed for are allowed
+            match recurrence.freq {
+                Frequency::Monthly | Frequency::Yearly if !recurrence.by_day.is_empty() => {
+                    return Err("BYDAY can only be used with a DAILY or WEEKLY rule".to_string());
+                },
+                Frequency::Daily | Frequency::Weekly if recurrence.by_month_day.is_some() => {
+                    return Err("BYMONTHDAY can only be used with a MONTHLY or YEARLY rule".to_string());
+                },
+                _ => {},
+            }
+            Ok(recurrence)
+        }
+    }
+
+    impl Serialize for Recurrence {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.rule())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Recurrence {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+        }
+    }
+
+    impl PartialEq for AppElement {
+        fn eq(&self, other: &AppElement) -> bool {
+            match (&self.uuid, &other.uuid) {
+                (Some(uuid), Some(other_uuid)) => uuid == other_uuid,
+                _ if self.id.is_some() || other.id.is_some() => self.id == other.id,
+                _ => self.handle != 0 && self.handle == other.handle,
+            }
+        }
+    }
+
+    /// Prefix of the labels of elements that are not synced yet, followed by their handle
+    const NEW_LABEL: &str = "new";
+
+    /// How many random IDs are tried before the free IDs are searched in order
+    const ID_ATTEMPTS: usize = 64;
+
+    /// Generates a random (version 4) UUID
+    fn new_uuid() -> String {
+        let mut bytes: [u8; 16] = rand::thread_rng().gen();
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        let hex: String = bytes.iter().map(|e| format!("{:02x}", e)).collect();
+        format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+    }
+
+    /// Format of the start and end of events
+    const EVENT_FORMAT: &str = "%a, %d %b %Y %H:%M";
+
+    /// Format of the days of all-day events
+    const ALL_DAY_FORMAT: &str = "%a, %d %b %Y";
+
+    /// Formats an optional unix timestamp as a local rfc2822 date
+    fn format_due(due: Option<u32>) -> String {
+        match due {
+            Some(due) => {
+                let due_timestamp: i64 = due.into();
+                match Utc.timestamp_opt(due_timestamp, 0) {
+                    LocalResult::None => "None".to_string(),
+                    LocalResult::Single(val) => val.with_timezone(&chrono::Local).to_rfc2822(),
+                    LocalResult::Ambiguous(val, _) => val.with_timezone(&chrono::Local).to_rfc2822(),
+                }
+            },
+            None => "None".to_string()
+        }
+    }
+
+    /// Returns the start of the local day of the timestamp, moved by the given
+    /// number of days
+    fn day_start(timestamp: u32, days: u64) -> u32 {
+        Local
+            .timestamp_opt(timestamp.into(), 0)
+            .single()
+            .and_then(|e| e.date_naive().checked_add_days(Days::new(days)))
+            .and_then(|e| e.and_hms_opt(0, 0, 0))
+            .and_then(|e| e.and_local_timezone(Local).earliest())
+            .and_then(|e| u32::try_from(e.timestamp()).ok())
+            .unwrap_or(timestamp)
+    }
+
+    /// Formats a number of seconds like '1d 2h 30m'
+    fn format_duration(seconds: u32) -> String {
+        let parts: Vec<String> = [(seconds / 86400, "d"), (seconds % 86400 / 3600, "h"), (seconds % 3600 / 60, "m")]
+            .iter()
+            .filter(|e| e.0 != 0)
+            .map(|e| format!("{}{}", e.0, e.1))
+            .collect();
+        match parts.is_empty() {
+            true => "0m".to_string(),
+            false => parts.join(" "),
+        }
+    }
 
-            let id: String = match self.id {
-                Some(id) => format!("{}", id),
-                None => "None".to_string()
+    /// Formats the time range of an event as local time, 'None' for tasks.
+    /// Only the dates are shown for all-day events, the end only with the
+    /// time if it is on the same day as the start
+    fn format_event(start: Option<u32>, end: Option<u32>, all_day: bool) -> String {
+        let local = |timestamp: u32| Local.timestamp_opt(timestamp.into(), 0).single();
+        let Some(start_time) = start.and_then(local) else {return "None".to_string()};
+        let end_time = end.and_then(local).filter(|e| *e >= start_time);
+        if all_day {
+            let first: String = start_time.format(ALL_DAY_FORMAT).to_string();
+            return match end_time.map(|e| (e.date_naive() - start_time.date_naive()).num_days() + 1) {
+                Some(days) if days > 1 => format!("{} - {} ({} days)", first, end_time.unwrap().format(ALL_DAY_FORMAT), days),
+                _ => format!("{} (all day)", first),
             };
+        }
+        let first: String = start_time.format(EVENT_FORMAT).to_string();
+        match end_time {
+            Some(end_time) => {
+                let last_format: &str = match end_time.date_naive() == start_time.date_naive() {
+                    true => "%H:%M",
+                    false => EVENT_FORMAT,
+                };
+                let duration: u32 = (end_time - start_time).num_seconds().try_into().unwrap_or(0);
+                format!("{} - {} ({})", first, end_time.format(last_format), format_duration(duration))
+            },
+            None => first,
+        }
+    }
+
+    /// The start, the end and whether an event takes whole days
+    pub type EventTimes = (Option<u32>, Option<u32>, bool);
+
+    /// Returns the times of an event as they are stored, all-day events start
+    /// at the start of their days and elements without a start are tasks.
+    /// None if the end is before the start
+    fn event_times(start: Option<u32>, end: Option<u32>, all_day: bool) -> Option<EventTimes> {
+        let times: EventTimes = match start {
+            Some(start) if all_day => (Some(day_start(start, 0)), end.map(|e| day_start(e, 0)), true),
+            Some(start) => (Some(start), end, false),
+            None => (None, None, false),
+        };
+        times.1.is_none_or(|end| Some(end) >= times.0).then_some(times)
+    }
+
+    /// Returns the end of an event starting at the given time and taking the
+    /// given number of seconds. All-day events take at least one whole day
+    pub fn event_end(start: u32, duration: u32, all_day: bool) -> u32 {
+        match all_day {
+            true => day_start(start, u64::from(duration.div_ceil(86400).max(1) - 1)),
+            false => start.saturating_add(duration),
+        }
+    }
+
+    /// Formats an optional completion timestamp, 'No' for open elements
+    fn format_completed(completed: Option<u32>) -> String {
+        match completed {
+            Some(_) => format_due(completed),
+            None => "No".to_string(),
+        }
+    }
+
+    /// Formats an optional recurrence rule, 'Never' for elements that don't repeat
+    fn format_recurrence(recurrence: Option<&Recurrence>) -> String {
+        match recurrence {
+            Some(recurrence) => recurrence.to_string(),
+            None => "Never".to_string(),
+        }
+    }
+
+    impl fmt::Display for AppElement {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let disp_due: String = format_due(self.due);
+
             write!(
                 f,
-                "ID: {}\nTitle: {}\nDescription: {}\nDue: {:#?}\nTags: {}\n",
-                id,
+                "ID: {}\nParent: {}\nTitle: {}\nDescription: {}\nDue: {:#?}\nWhen: {}\nRepeats: {}\nCompleted: {}\nPriority: {}\nTags: {}\n",
+                self.label(),
+                self.parent_label(),
                 &self.title,
                 &self.description,
                 disp_due,
+                format_event(self.start, self.end, self.all_day),
+                format_recurrence(self.recurrence.as_ref()),
+                format_completed(self.completed),
+                self.priority,
                 &self.tags.clone().unwrap_or(AppElementTags::empty()).tags.join(" "),
             )
         }
@@ -95,12 +586,22 @@ pub(crate) mod data_types {
         pub fn new(id: Option<u16>, title: String, description: String, due: Option<u32>, tags: Vec<String>) -> Self {
             Self {
                 id,
+                uuid: Some(new_uuid()),
                 title,
                 description,
                 due,
+                start: None,
+                end: None,
+                all_day: false,
+                completed: None,
+                priority: Priority::None,
+                recurrence: None,
+                parent: None,
                 tags: Some(AppElementTags::new(tags)),
                 removed: false,
                 modified: false,
+                handle: 0,
+                parent_handle: 0,
             }
         }
 
@@ -112,6 +613,54 @@ pub(crate) mod data_types {
             self.modified = true;
         }
 
+        pub fn id(&self) -> Option<u16> {
+            self.id
+        }
+
+        /// Makes a new element a subtask of the given element of the local state
+        pub fn with_parent(mut self, parent: Option<&AppElement>) -> Self {
+            self.parent = parent.and_then(|e| e.id);
+            self.parent_handle = parent.map(|e| e.handle).unwrap_or(0);
+            self
+        }
+
+        pub fn handle(&self) -> u32 {
+            self.handle
+        }
+
+        pub fn parent_handle(&self) -> u32 {
+            self.parent_handle
+        }
+
+        /// Returns the label of the parent element, like the parent would show it
+        fn parent_label(&self) -> String {
+            match self.parent {
+                Some(parent) => parent.to_string(),
+                None if self.parent_handle != 0 => format!("{}{}", NEW_LABEL, self.parent_handle),
+                None => "None".to_string(),
+            }
+        }
+
+        /// Returns how the element is shown to and selected by the user,
+        /// the ID or e.g. 'new1' for elements that are not synced yet
+        pub fn label(&self) -> String {
+            match self.id {
+                Some(id) => id.to_string(),
+                None => format!("{}{}", NEW_LABEL, self.handle),
+            }
+        }
+
+        /// Returns whether both elements are the same entry of the registry.
+        /// The IDs have to match and the UUIDs must not contradict each other,
+        /// elements of older clients without a UUID are told apart by ID only
+        pub fn same_entry(&self, other: &AppElement) -> bool {
+            let uuids_match: bool = match (&self.uuid, &other.uuid) {
+                (Some(uuid), Some(other_uuid)) => uuid == other_uuid,
+                _ => true,
+            };
+            self.id.is_some() && self.id == other.id && uuids_match
+        }
+
         pub fn title(&self) -> String {
             self.clone().title
         }
@@ -124,6 +673,137 @@ pub(crate) mod data_types {
             self.due
         }
 
+        /// Turns a new element into an event, see `set_event`. An end before
+        /// the start is dropped
+        pub fn with_event(mut self, start: Option<u32>, end: Option<u32>, all_day: bool) -> Self {
+            (self.start, self.end, self.all_day) = event_times(start, end, all_day)
+                .or(event_times(start, None, all_day))
+                .unwrap_or_default();
+            self
+        }
+
+        pub fn start(&self) -> Option<u32> {
+            self.start
+        }
+
+        pub fn end(&self) -> Option<u32> {
+            self.end
+        }
+
+        pub fn is_all_day(&self) -> bool {
+            self.all_day
+        }
+
+        /// Returns whether the element is an event rather than a task
+        pub fn is_event(&self) -> bool {
+            self.start.is_some()
+        }
+
+        /// Makes the element an event with the given times, or a task if there
+        /// is no start. All-day events are moved to the start of their days.
+        /// Returns false, leaving the element as it is, if the end is before the start
+        pub fn set_event(&mut self, start: Option<u32>, end: Option<u32>, all_day: bool) -> bool {
+            let Some((start, end, all_day)) = event_times(start, end, all_day) else {return false};
+            if (start, end, all_day) != (self.start, self.end, self.all_day) {
+                (self.start, self.end, self.all_day) = (start, end, all_day);
+                self.modified = true;
+            }
+            true
+        }
+
+        /// Returns when the element takes place, the start of events and the
+        /// due date of tasks
+        pub fn when(&self) -> Option<u32> {
+            self.start.or(self.due)
+        }
+
+        /// Returns when the element is over, the end of events and the due
+        /// date of tasks. All-day events are over at the end of their last day
+        pub fn over_at(&self) -> Option<u32> {
+            let Some(start) = self.start else {return self.due};
+            let end: u32 = self.end.unwrap_or(start);
+            match self.all_day {
+                true => Some(day_start(end, 1)),
+                false => Some(end),
+            }
+        }
+
+        /// Returns whether the element is an event taking place at some time
+        /// between the given timestamps
+        pub fn overlaps(&self, from: u32, to: u32) -> bool {
+            self.start.is_some_and(|start| start < to) && self.over_at().is_some_and(|end| end > from)
+        }
+
+        /// Sets the priority of a new element
+        pub fn with_priority(mut self, priority: Priority) -> Self {
+            self.priority = priority;
+            self
+        }
+
+        pub fn priority(&self) -> Priority {
+            self.priority
+        }
+
+        pub fn set_priority(&mut self, priority: Priority) {
+            if priority != self.priority {
+                self.priority = priority;
+                self.modified = true;
+            }
+        }
+
+        /// Sets the recurrence rule of a new element
+        pub fn with_recurrence(mut self, recurrence: Option<Recurrence>) -> Self {
+            self.recurrence = recurrence;
+            self
+        }
+
+        pub fn recurrence(&self) -> Option<&Recurrence> {
+            self.recurrence.as_ref()
+        }
+
+        pub fn set_recurrence(&mut self, recurrence: Option<Recurrence>) {
+            if recurrence != self.recurrence {
+                self.recurrence = recurrence;
+                self.modified = true;
+            }
+        }
+
+        /// Returns a new element for the first occurrence after the given time,
+        /// which takes over the recurrence rule. None if the rule ended
+        fn next_occurrence(&self, after: u32) -> Option<AppElement> {
+            let anchor: u32 = self.due.or(self.start).unwrap_or(after);
+            let (next, recurrence) = self.recurrence.as_ref()?.next_after(anchor, after)?;
+            // Events keep their length, the next occurrence is always after the anchor
+            let shift = |e: u32| e.saturating_add(next.saturating_sub(anchor));
+            let due: Option<u32> = match self.start {
+                Some(_) => self.due.map(shift),
+                None => Some(next),
+            };
+            let mut element: AppElement = AppElement::new(None, self.title(), self.description(), due, self.tags())
+                .with_event(self.start.map(shift), self.end.map(shift), self.all_day)
+                .with_priority(self.priority)
+                .with_recurrence(Some(recurrence));
+            // Subtasks stay with their parent
+            element.parent = self.parent;
+            element.parent_handle = self.parent_handle;
+            Some(element)
+        }
+
+        pub fn is_completed(&self) -> bool {
+            self.completed.is_some()
+        }
+
+        /// Marks the element as completed now, or as open again.
+        /// Returns whether the state of the element changed
+        fn set_completed(&mut self, completed: bool) -> bool {
+            if completed == self.is_completed() {
+                return false;
+            }
+            self.completed = completed.then(|| Utc::now().timestamp().try_into().unwrap_or(u32::MAX));
+            self.modified = true;
+            true
+        }
+
         pub fn tags(&self) -> Vec<String> {
             match &self.tags {
                 Some(e) => {e.clone().tags},
@@ -135,7 +815,7 @@ pub(crate) mod data_types {
         /// followed by the tags as a single lowercase string, this is designed
         /// for usage of searching and filtering
         pub fn get_text(&self) -> String {
-            return String::new() 
+            (String::new()
                 + &self.title
                 + " "
                 + &self.description
@@ -143,22 +823,29 @@ pub(crate) mod data_types {
                 + &self.tags.clone()
                     .unwrap_or(AppElementTags::empty())
                     .tags
-                    .join(" ")
-                .to_lowercase();
+                    .join(" "))
+                .to_lowercase()
         }
 
         /// Generates a new ID for this element. The id will not be in existing ids
         /// Updates the self element and the existing ids
-        /// Returns the new id
-        pub fn generate_id(&mut self, existing_ids: &mut Vec<u16>) -> u16 {
+        /// Returns the new id, or None if every ID is taken already
+        pub fn generate_id(&mut self, existing_ids: &mut Vec<u16>) -> Option<u16> {
             let mut rng = rand::thread_rng();
-            let mut new_id: u16 = 0;
-            while new_id == 0 || existing_ids.iter().any(|&i| i==new_id) {
-                new_id = rng.gen::<u16>();
+            let new_id: u16 = (0..ID_ATTEMPTS)
+                .map(|_| rng.gen_range(1..=u16::MAX))
+                .find(|e| !existing_ids.contains(e))
+                .or_else(|| {
+                    // Random tries keep failing on an almost full registry
+                    let taken: HashSet<u16> = existing_ids.iter().copied().collect();
+                    (1..=u16::MAX).find(|e| !taken.contains(e))
+                })?;
+            if self.uuid.is_none() {
+                self.uuid = Some(new_uuid());
             }
             self.id = Some(new_id);
             existing_ids.push(new_id);
-            new_id
+            Some(new_id)
         }
 
         /// Writes the element using the given quick xml writer
@@ -169,11 +856,12 @@ pub(crate) mod data_types {
                 return Ok(());
             }
             if with_head {
-                writer.write_event(Event::Start(
-                    BytesStart::new("entry")
-                        .with_attributes([Attribute::from(("id", self.id.unwrap().to_string().as_str()))])
-                    )
-                )?;
+                let mut head: BytesStart = BytesStart::new("entry")
+                    .with_attributes([Attribute::from(("id", self.id.unwrap().to_string().as_str()))]);
+                if let Some(uuid) = &self.uuid {
+                    head.push_attribute(("uuid", uuid.as_str()));
+                }
+                writer.write_event(Event::Start(head))?;
             }
             writer.write_event(Event::Start(BytesStart::new("name")))?;
             writer.write_event(Event::Text(BytesText::new(&self.title)))?;
@@ -183,16 +871,58 @@ pub(crate) mod data_types {
             writer.write_event(Event::Text(BytesText::new(&self.description)))?;
             writer.write_event(Event::End(BytesEnd::new("description")))?;
 
-            if self.due.is_some() {
+            if let Some(due) = self.due {
                 writer.write_event(Event::Start(BytesStart::new("due")))?;
-                writer.write_event(Event::Text(BytesText::new(&self.due.unwrap().to_string())))?;
+                writer.write_event(Event::Text(BytesText::new(&due.to_string())))?;
                 writer.write_event(Event::End(BytesEnd::new("due")))?;
             }
 
+            if let Some(start) = self.start {
+                writer.write_event(Event::Start(BytesStart::new("start")))?;
+                writer.write_event(Event::Text(BytesText::new(&start.to_string())))?;
+                writer.write_event(Event::End(BytesEnd::new("start")))?;
+            }
+
+            if let Some(end) = self.end {
+                writer.write_event(Event::Start(BytesStart::new("end")))?;
+                writer.write_event(Event::Text(BytesText::new(&end.to_string())))?;
+                writer.write_event(Event::End(BytesEnd::new("end")))?;
+            }
+
+            if self.all_day {
+                writer.write_event(Event::Start(BytesStart::new("allday")))?;
+                writer.write_event(Event::Text(BytesText::new("true")))?;
+                writer.write_event(Event::End(BytesEnd::new("allday")))?;
+            }
+
+            if let Some(completed) = self.completed {
+                writer.write_event(Event::Start(BytesStart::new("completed")))?;
+                writer.write_event(Event::Text(BytesText::new(&completed.to_string())))?;
+                writer.write_event(Event::End(BytesEnd::new("completed")))?;
+            }
+
+            if !self.priority.is_none() {
+                writer.write_event(Event::Start(BytesStart::new("priority")))?;
+                writer.write_event(Event::Text(BytesText::new(self.priority.name())))?;
+                writer.write_event(Event::End(BytesEnd::new("priority")))?;
+            }
+
+            if let Some(recurrence) = &self.recurrence {
+                writer.write_event(Event::Start(BytesStart::new("recurrence")))?;
+                writer.write_event(Event::Text(BytesText::new(&recurrence.rule())))?;
+                writer.write_event(Event::End(BytesEnd::new("recurrence")))?;
+            }
+
+            if let Some(parent) = self.parent {
+                writer.write_event(Event::Start(BytesStart::new("parent")))?;
+                writer.write_event(Event::Text(BytesText::new(&parent.to_string())))?;
+                writer.write_event(Event::End(BytesEnd::new("parent")))?;
+            }
+
             writer.write_event(Event::Start(BytesStart::new("tags")))?;
             self.tags.clone().unwrap_or(AppElementTags::empty()).tags.iter().for_each(|e| {
                 writer.write_event(Event::Start(BytesStart::new("tag"))).unwrap_or(());
-                writer.write_event(Event::Text(BytesText::new(&e))).unwrap_or(());
+                writer.write_event(Event::Text(BytesText::new(e))).unwrap_or(());
                 writer.write_event(Event::End(BytesEnd::new("tag"))).unwrap_or(());
             });
             writer.write_event(Event::End(BytesEnd::new("tags")))?;
@@ -204,81 +934,1467 @@ pub(crate) mod data_types {
             Ok(())
         }
 
-        pub fn to_row(&self) -> Row {
-            let disp_due: String = match self.due {
-                Some(due) => {
-                    let due_timestamp: i64 = due.into();
-                    let utc_due: String = match Utc.timestamp_opt(due_timestamp, 0) {
-                        LocalResult::None => "None".to_string(),
-                        LocalResult::Single(val) => val.with_timezone(&chrono::Local).to_rfc2822(),
-                        LocalResult::Ambiguous(val, _) => val.with_timezone(&chrono::Local).to_rfc2822(),
-                    };
-                    //let offset = Local::now().offset();
-                    utc_due
+        /// Returns whether the given field has the same value in both elements
+        fn field_eq(&self, other: &AppElement, field: MergeField) -> bool {
+            match field {
+                MergeField::Title => self.title == other.title,
+                MergeField::Description => self.description == other.description,
+                MergeField::Due => self.due == other.due,
+                MergeField::Event => (self.start, self.end, self.all_day) == (other.start, other.end, other.all_day),
+                MergeField::Completed => self.completed == other.completed,
+                MergeField::Priority => self.priority == other.priority,
+                MergeField::Recurrence => self.recurrence == other.recurrence,
+                MergeField::Parent => self.parent == other.parent,
+                MergeField::Tags => self.tags() == other.tags(),
+            }
+        }
 
+        /// Overwrites the given field with the value of the other element
+        fn take_field(&mut self, other: &AppElement, field: MergeField) {
+            match field {
+                MergeField::Title => self.title = other.title.clone(),
+                MergeField::Description => self.description = other.description.clone(),
+                MergeField::Due => self.due = other.due,
+                MergeField::Event => (self.start, self.end, self.all_day) = (other.start, other.end, other.all_day),
+                MergeField::Completed => self.completed = other.completed,
+                MergeField::Priority => self.priority = other.priority,
+                MergeField::Recurrence => self.recurrence = other.recurrence.clone(),
+                MergeField::Parent => {
+                    // The local link is found again by the ID of the parent
+                    self.parent = other.parent;
+                    self.parent_handle = 0;
                 },
-                None => "None".to_string(),
-            };
+                MergeField::Tags => self.tags = other.tags.clone(),
+            }
+        }
+
+        /// Returns the given field in a human readable form
+        pub fn field_string(&self, field: MergeField) -> String {
+            match field {
+                MergeField::Title => self.title(),
+                MergeField::Description => self.description(),
+                MergeField::Due => format_due(self.due),
+                MergeField::Event => format_event(self.start, self.end, self.all_day),
+                MergeField::Completed => format_completed(self.completed),
+                MergeField::Priority => self.priority.to_string(),
+                MergeField::Recurrence => format_recurrence(self.recurrence.as_ref()),
+                MergeField::Parent => self.parent_label(),
+                MergeField::Tags => self.tags().join(" "),
+            }
+        }
+
+        /// Returns the element as a standalone 'entry' XML document
+        fn to_xml(&self) -> String {
+            let mut writer = Writer::new(Cursor::new(Vec::new()));
+            self.write(&mut writer, true).unwrap_or(());
+            String::from_utf8(writer.into_inner().into_inner()).unwrap_or_default()
+        }
 
-            let id = match self.id {
-                Some(id) => format!("{}", id),
-                None => "None".to_string()
+        pub fn to_row(&self) -> Row {
+            self.row_with_title(self.title())
+        }
+
+        /// Returns the table row of the element, showing the given title
+        fn row_with_title(&self, title: String) -> Row {
+            let disp_when: String = match (self.start, self.due) {
+                (Some(_), Some(due)) => format!("{}, due {}", format_event(self.start, self.end, self.all_day), format_due(Some(due))),
+                (Some(_), None) => format_event(self.start, self.end, self.all_day),
+                (None, _) => format_due(self.due),
+            };
+            let disp_due: String = match &self.recurrence {
+                Some(recurrence) => format!("{} ({})", disp_when, recurrence),
+                None => disp_when,
             };
-            if self.removed {
+            let disp_completed: String = match self.completed {
+                Some(_) => format_completed(self.completed),
+                None => String::new(),
+            };
+
+            let id: String = self.label();
+            let mut row: Row = if self.removed {
                 row![
                     Fri =>
                     id,
-                    self.title,
+                    title,
                     self.description,
                     disp_due,
+                    disp_completed,
                 ]
             } else if self.modified {
                 row![
                     Fyi =>
                     id,
-                    self.title,
+                    title,
                     self.description,
                     disp_due,
+                    disp_completed,
                 ]
             } else if self.id.is_none() {
                 row![
                     Fbi =>
                     id,
-                    self.title,
+                    title,
+                    self.description,
+                    disp_due,
+                    disp_completed,
+                ]
+            } else if self.completed.is_some() {
+                row![
+                    Fg =>
+                    id,
+                    title,
                     self.description,
                     disp_due,
+                    disp_completed,
                 ]
             } else {
                 row![
                     id,
-                    self.title,
+                    title,
                     self.description,
                     disp_due,
+                    disp_completed,
                 ]
+            };
+            row.add_cell(Cell::new(&self.priority.to_string()).style_spec(self.priority.style()));
+            row
+        }
+    }
+
+    /// Builds a table of the given elements
+    pub fn elements_table<'a>(elements: impl Iterator<Item = &'a AppElement>) -> Table {
+        let mut table: Table = Table::new();
+        table.set_titles(row!["ID", "Title", "Description", "Due / When", "Completed", "Priority"]);
+        elements.for_each(|e| {
+            table.add_row(e.to_row());
+        });
+        table
+    }
+
+    /// The fields of an element that are merged individually during sync
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum MergeField {
+        Title,
+        Description,
+        Due,
+        Event,
+        Completed,
+        Priority,
+        Recurrence,
+        Parent,
+        Tags,
+    }
+
+    impl MergeField {
+        pub const ALL: [MergeField; 9] = [
+            Self::Title,
+            Self::Description,
+            Self::Due,
+            Self::Event,
+            Self::Completed,
+            Self::Priority,
+            Self::Recurrence,
+            Self::Parent,
+            Self::Tags,
+        ];
+    }
+
+    impl fmt::Display for MergeField {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let displ: &str = match self {
+                Self::Title => "Title",
+                Self::Description => "Description",
+                Self::Due => "Due",
+                Self::Event => "Event",
+                Self::Completed => "Completed",
+                Self::Priority => "Priority",
+                Self::Recurrence => "Recurrence",
+                Self::Parent => "Parent",
+                Self::Tags => "Tags",
+            };
+            write!(f, "{}", displ)
+        }
+    }
+
+    /// A field that was changed differently on the server and locally since
+    /// the last sync
+    pub struct Conflict {
+        pub id: u16,
+        pub field: MergeField,
+        pub base: String,
+        pub local: String,
+        pub remote: String,
+    }
+
+    /// Which side of a conflict should be kept
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Resolution {
+        Local,
+        Remote,
+    }
+
+    /// Decides how conflicts that can not be merged automatically are resolved
+    pub trait SyncHandler {
+        fn resolve(&mut self, conflict: &Conflict) -> Resolution;
+
+        /// Gets to see the changes before they are sent to the server, also
+        /// when there are none. Returning false aborts the sync and leaves the
+        /// local state as it was
+        fn review(&mut self, _plan: &SyncPlan) -> bool {
+            true
+        }
+    }
+
+    /// A single field as it is before and after a sync
+    pub struct FieldChange {
+        pub field: MergeField,
+        pub before: String,
+        pub after: String,
+    }
+
+    /// An element that is about to be changed on the server
+    pub struct PlannedChange {
+        pub id: Option<u16>,
+        pub title: String,
+        pub fields: Vec<FieldChange>,
+    }
+
+    /// The changes a sync is about to send to the server
+    #[derive(Default)]
+    pub struct SyncPlan {
+        pub created: Vec<PlannedChange>,
+        pub modified: Vec<PlannedChange>,
+        pub deleted: Vec<PlannedChange>,
+    }
+
+    impl SyncPlan {
+        /// Returns whether nothing is going to be sent
+        pub fn is_empty(&self) -> bool {
+            self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+        }
+    }
+
+    impl fmt::Display for SyncPlan {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            if self.is_empty() {
+                return write!(f, "Nothing to upload");
+            }
+            let mut lines: Vec<String> = Vec::new();
+            let mut push = |sign: &str, change: &PlannedChange| {
+                let id: String = change.id.map(|e| e.to_string()).unwrap_or("new".to_string());
+                lines.push(format!("{} {} \"{}\"", sign, id, change.title));
+                change.fields.iter().for_each(|e| {
+                    lines.push(format!("    {}: {:?} -> {:?}", e.field, e.before, e.after));
+                });
+            };
+            self.created.iter().for_each(|e| push("+", e));
+            self.modified.iter().for_each(|e| push("~", e));
+            self.deleted.iter().for_each(|e| push("-", e));
+            write!(f, "{}", lines.join("\n"))
+        }
+    }
+
+    /// What changed on the server, as found out by a sync
+    #[derive(Debug, Default)]
+    pub struct SyncReport {
+        /// Elements that were created on the server
+        pub added: Vec<u16>,
+        /// Elements that were changed on the server
+        pub updated: Vec<u16>,
+        /// Elements that were deleted on the server
+        pub deleted: Vec<u16>,
+        /// Elements deleted on the server that were edited locally and are created again
+        pub revived: Vec<u16>,
+    }
+
+    impl SyncReport {
+        /// Returns whether the server had no changes for the local state
+        pub fn is_empty(&self) -> bool {
+            self.added.is_empty()
+                && self.updated.is_empty()
+                && self.deleted.is_empty()
+                && self.revived.is_empty()
+        }
+    }
+
+    impl fmt::Display for SyncReport {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            if self.is_empty() {
+                return write!(f, "No changes on the server");
+            }
+            let join = |ids: &Vec<u16>| ids
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            let mut lines: Vec<String> = Vec::new();
+            if !self.added.is_empty() {
+                lines.push(format!("New on the server: {}", join(&self.added)));
+            }
+            if !self.updated.is_empty() {
+                lines.push(format!("Updated on the server: {}", join(&self.updated)));
+            }
+            if !self.deleted.is_empty() {
+                lines.push(format!("Deleted on the server: {}", join(&self.deleted)));
+            }
+            if !self.revived.is_empty() {
+                lines.push(format!("Deleted on the server but edited locally, created again: {}", join(&self.revived)));
+            }
+            write!(f, "{}", lines.join("\n"))
+        }
+    }
+
+    /// Whether the locally cached registry still matches the server
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Freshness {
+        /// The server was not asked yet or could not be reached
+        Unknown,
+        /// The server has the same registry as the last sync
+        Fresh,
+        /// The registry on the server changed since the last sync
+        Stale,
+    }
+
+    impl fmt::Display for Freshness {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let displ: &str = match self {
+                Self::Unknown => "unknown",
+                Self::Fresh => "fresh",
+                Self::Stale => "stale",
+            };
+            write!(f, "{}", displ)
+        }
+    }
+
+    /// An element as it is stored in the cache, including its local flags
+    #[derive(Serialize, Deserialize)]
+    struct CachedElement {
+        removed: bool,
+        modified: bool,
+        /// Missing in caches of older versions, a new handle is assigned then
+        #[serde(default)]
+        handle: u32,
+        /// Only needed for parents that are not synced yet, others are found by their ID
+        #[serde(default)]
+        parent_handle: u32,
+        element: AppElement,
+    }
+
+    /// The local state as it is persisted between runs
+    #[derive(Serialize, Deserialize, Default)]
+    pub struct AppCache {
+        /// Server and user the cached state belongs to
+        server_address: String,
+        username: String,
+        /// Unix timestamp of the last successful sync
+        last_sync: Option<i64>,
+        /// Hash of the registry on the server after the last successful sync
+        registry_hash: Option<String>,
+        /// Whether the server accepts change sets, unknown before the first upload
+        delta_upload: Option<bool>,
+        /// The last handle given to an element, so labels are not reused across runs
+        #[serde(default)]
+        last_handle: u32,
+        elements: Vec<CachedElement>,
+        base: Vec<AppElement>,
+    }
+
+    /// The kind of change a journaled operation makes on the server
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Operation {
+        Create,
+        Edit,
+        Delete,
+    }
+
+    /// An operation that was about to be sent to the server
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct JournalEntry {
+        #[serde(rename = "@seq")]
+        seq: u64,
+        #[serde(rename = "@kind")]
+        kind: Operation,
+        entry: AppElement,
+    }
+
+    #[derive(Serialize, Deserialize, Default)]
+    #[serde(rename = "journal")]
+    struct JournalFile {
+        /// Server and user the operations were sent to
+        #[serde(rename = "@server")]
+        server_address: String,
+        #[serde(rename = "@user")]
+        username: String,
+        #[serde(rename = "op", default)]
+        entries: Vec<JournalEntry>,
+    }
+
+    /// Operations that were sent to the server without a confirmation yet.
+    /// Every operation is written to disk before it is sent, so it can be
+    /// replayed if the app stops before it learns whether the server got it
+    pub struct Journal {
+        path: PathBuf,
+        file: JournalFile,
+    }
+
+    impl Journal {
+        /// Opens the journal at the given path, an unreadable or missing
+        /// journal is started empty
+        pub fn open(path: PathBuf) -> Self {
+            let file: JournalFile = fs::read_to_string(&path)
+                .ok()
+                .and_then(|e| from_str::<JournalFile>(&e).ok())
+                .unwrap_or_default();
+            Self {
+                path,
+                file,
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.file.entries.is_empty()
+        }
+
+        /// Writes the given operations to disk, before they are sent. Earlier
+        /// operations on the same element under another ID are dropped, they
+        /// belong to an attempt that was given up
+        fn record(&mut self, operations: Vec<(Operation, AppElement)>) -> AppResult<()> {
+            if operations.is_empty() {
+                return Ok(());
+            }
+            self.file.entries.retain(|e| {
+                !operations.iter().any(|(_, o)| o.uuid.is_some() && o.uuid == e.entry.uuid && o.id != e.entry.id)
+            });
+            let last: u64 = self.file.entries.iter().map(|e| e.seq).max().unwrap_or(0);
+            for (seq, (kind, entry)) in (last + 1..).zip(operations) {
+                self.file.entries.push(JournalEntry {
+                    seq,
+                    kind,
+                    entry,
+                });
+            }
+            self.persist()
+        }
+
+        /// Drops all operations on the element with the given ID, as the server
+        /// confirmed them
+        fn confirm(&mut self, id: u16) -> AppResult<()> {
+            let len: usize = self.file.entries.len();
+            self.file.entries.retain(|e| e.entry.id != Some(id));
+            if self.file.entries.len() == len {
+                return Ok(());
+            }
+            self.persist()
+        }
+
+        /// Drops all operations, as the server confirmed them
+        fn clear(&mut self) -> AppResult<()> {
+            if self.is_empty() {
+                return Ok(());
+            }
+            self.file.entries.clear();
+            self.persist()
+        }
+
+        /// Replaces the journal on disk. The new journal is written next to the
+        /// old one and moved over it, so a crash never leaves half a journal
+        fn persist(&self) -> AppResult<()> {
+            if self.is_empty() {
+                return match fs::remove_file(&self.path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(AppError::Journal(e)),
+                    _ => Ok(()),
+                };
+            }
+            let xml: String = quick_xml::se::to_string(&self.file)
+                .map_err(|e| AppError::Journal(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+            write_atomically(&self.path, &xml).map_err(AppError::Journal)
+        }
+    }
+
+    /// Returns the given path with a suffix added to the file name
+    fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut path = path.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    }
+
+    /// Replaces the file with the given content. The content is written next to
+    /// the file and moved over it, so a crash never leaves half a file
+    fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+        let tmp: PathBuf = with_suffix(path, ".tmp");
+        let mut file: fs::File = fs::File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Lists the operations that turn the fetched registry into the one that
+    /// is about to be uploaded
+    fn pending_operations(fetched: &[AppElement], uploaded: &[AppElement]) -> Vec<(Operation, AppElement)> {
+        let changed = uploaded.iter().filter_map(|u| {
+            match fetched.iter().find(|f| f.id == u.id) {
+                None => Some((Operation::Create, u.clone())),
+                Some(f) if !MergeField::ALL.iter().all(|field| f.field_eq(u, *field)) => Some((Operation::Edit, u.clone())),
+                Some(_) => None,
+            }
+        });
+        let deleted = fetched
+            .iter()
+            .filter(|f| !uploaded.iter().any(|u| u.id == f.id))
+            .map(|f| (Operation::Delete, f.clone()));
+        changed.chain(deleted).collect()
+    }
+
+    /// Hashes the content of the given registry entries, independent of their
+    /// order and formatting. Covers every field that is merged and the UUID.
+    /// Uses FNV-1a so the hash is stable between runs
+    fn registry_hash(entries: &[AppElement]) -> String {
+        let mut sorted: Vec<&AppElement> = entries.iter().collect();
+        sorted.sort_by_key(|e| e.id);
+        let content: String = sorted
+            .iter()
+            .map(|e| format!(
+                "{:?}\u{1f}{:?}\u{1f}{}\u{1f}{}\u{1f}{:?}\u{1f}{:?}\u{1f}{:?}\u{1f}{}\u{1f}{:?}\u{1f}{}\u{1f}{:?}\u{1f}{:?}\u{1f}{}\u{1e}",
+                e.id,
+                e.uuid,
+                e.title,
+                e.description,
+                e.due,
+                e.start,
+                e.end,
+                e.all_day,
+                e.completed,
+                e.priority.name(),
+                e.recurrence.as_ref().map(|r| r.rule()),
+                e.parent,
+                e.tags().join("\u{1f}"),
+            ))
+            .collect();
+        content_hash(&content)
+    }
+
+    /// Hashes the given text with FNV-1a
+    fn content_hash(content: &str) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        content.bytes().for_each(|b| {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// Reads the ID attribute of an entry tag
+    fn entry_id(tag: &BytesStart, reader: &Reader<&[u8]>) -> Option<u16> {
+        tag
+            .attributes()
+            .filter_map(|e| e.ok())
+            .find(|e| e.key.local_name().as_ref() == b"id")
+            .and_then(|e| e.decode_and_unescape_value(reader).ok())
+            .and_then(|e| e.parse::<u16>().ok())
+    }
+
+    /// Cuts the entries with the given IDs out of a XML document, keeping
+    /// everything inside of them as it is
+    fn entry_fragments(xml: &str, ids: &[u16]) -> Result<Vec<(u16, String)>, quick_xml::Error> {
+        let mut reader = Reader::from_str(xml);
+        let mut fragments: Vec<(u16, String)> = Vec::new();
+        let mut writer: Option<(u16, Writer<Cursor<Vec<u8>>>)> = None;
+        let mut depth: usize = 0;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) if writer.is_none() && e.name().as_ref() == b"entry" => {
+                    let Some(id) = entry_id(&e, &reader).filter(|id| ids.contains(id)) else {continue};
+                    let mut entry_writer = Writer::new(Cursor::new(Vec::new()));
+                    entry_writer.write_event(Event::Start(e.to_owned()))?;
+                    writer = Some((id, entry_writer));
+                    depth = 1;
+                },
+                Event::Eof => break,
+                e => if let Some((_, entry_writer)) = writer.as_mut() {
+                    match e {
+                        Event::Start(_) => depth += 1,
+                        Event::End(_) => depth -= 1,
+                        _ => (),
+                    }
+                    entry_writer.write_event(e)?;
+                    if depth == 0 {
+                        if let Some((id, entry_writer)) = writer.take() {
+                            fragments.push((id, String::from_utf8_lossy(&entry_writer.into_inner().into_inner()).to_string()));
+                        }
+                    }
+                },
+            }
+        }
+        Ok(fragments)
+    }
+
+    /// The entries a sync creates, modifies and deletes, in the form the
+    /// server can apply to its registry without receiving the whole document
+    #[derive(Debug, Default)]
+    struct ChangeSet {
+        created: Vec<(u16, String)>,
+        modified: Vec<(u16, String)>,
+        deleted: Vec<u16>,
+    }
+
+    impl ChangeSet {
+        /// Collects the given operations, taking the created and modified
+        /// entries from the registry that is about to be uploaded
+        fn new(operations: &[(Operation, AppElement)], answer: &str) -> AppResult<Self> {
+            let ids = |kind: Operation| -> Vec<u16> {
+                operations
+                    .iter()
+                    .filter(|e| e.0 == kind)
+                    .filter_map(|e| e.1.id)
+                    .collect()
+            };
+
+            Ok(Self {
+                created: entry_fragments(answer, &ids(Operation::Create))?,
+                modified: entry_fragments(answer, &ids(Operation::Edit))?,
+                deleted: ids(Operation::Delete),
+            })
+        }
+
+        fn to_xml(&self) -> String {
+            let deleted: String = self.deleted
+                .iter()
+                .map(|e| format!("<delete id=\"{}\"/>", e))
+                .collect();
+            let entries = |entries: &[(u16, String)]| -> String {
+                entries.iter().map(|e| e.1.as_str()).collect()
+            };
+            format!(
+                "<changes><create>{}</create><modify>{}</modify>{}</changes>",
+                entries(&self.created),
+                entries(&self.modified),
+                deleted
+            )
+        }
+    }
+
+    /// How often a sync merges again if the registry changed on the server
+    /// while it was running
+    const SYNC_ATTEMPTS: usize = 3;
+
+    /// Whether a request may be sent again after it possibly reached the server
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Idempotency {
+        /// Sending the request twice has the same effect as sending it once
+        Idempotent,
+        /// The request is only sent again if it never reached the server
+        NonIdempotent,
+    }
+
+    /// The version of the registry an upload is based on
+    #[derive(Debug, Clone)]
+    enum Revision {
+        /// The ETag the server sent with the registry
+        ETag(String),
+        /// The hash of the registry, for servers that don't send an ETag
+        Hash(String),
+    }
+
+    /// Fails if the server did not answer with a success status
+    fn check_status(res: &Response) -> AppResult<()> {
+        match AppError::from_status(res.status().as_u16()) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails if the server did not accept the credentials
+    fn check_auth(res: &Response) -> AppResult<()> {
+        match AppError::from_status(res.status().as_u16()) {
+            Some(e @ AppError::Auth(_)) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the server did not answer with XML
+    fn check_xml_content(res: &Response) -> AppResult<()> {
+        let content_type: &str = res
+            .headers()
+            .get("content-type")
+            .and_then(|e| e.to_str().ok())
+            .unwrap_or("");
+        if content_type.starts_with("text/xml") || content_type.starts_with("application/xml") {
+            Ok(())
+        } else {
+            Err(AppError::ContentType(content_type.to_string()))
+        }
+    }
+
+    /// Where the registry is kept and how it is read and changed. Entries
+    /// are passed in the XML format of the registry
+    trait Backend {
+        /// Returns the whole registry, together with its revision if it is known
+        async fn fetch(&mut self) -> AppResult<(String, Option<String>)>;
+        /// Replaces the whole registry, if it is still at the given revision
+        async fn update(&mut self, registry: String, revision: &Revision) -> AppResult<()>;
+        /// Applies the given changes, if the registry is still at the given revision.
+        /// Returns false if the backend does not support change sets
+        async fn apply(&mut self, changes: &ChangeSet, revision: &Revision) -> AppResult<bool>;
+        /// Returns the entry with the given ID, if there is one
+        async fn get_by_id(&mut self, id: u16) -> AppResult<Option<String>>;
+        /// Creates the given entry, returns whether it was accepted
+        async fn add(&mut self, entry: String) -> AppResult<bool>;
+        /// Replaces the entry with the given ID, returns whether it was accepted
+        async fn edit(&mut self, id: u16, entry: String) -> AppResult<bool>;
+        /// Deletes the entry with the given ID, returns whether it is gone
+        async fn delete(&mut self, id: u16) -> AppResult<bool>;
+    }
+
+    /// A Freemind server, speaking XML over HTTP
+    struct HttpBackend {
+        config: AppConfig,
+        client: Option<Client>,
+        /// Whether retries are done without telling the user
+        quiet: bool,
+    }
+
+    impl HttpBackend {
+        fn new(config: &AppConfig) -> Self {
+            Self {
+                config: config.clone(),
+                client: None,
+                quiet: false,
+            }
+        }
+
+        fn handle_empty_client(&mut self) -> AppResult<&Client> {
+            if self.client.is_none() {
+                self.client = Some(
+                    Client::builder()
+                        .user_agent("Freemind CLI")
+                        .timeout(Duration::from_secs(self.config.network.request_timeout))
+                        .connect_timeout(Duration::from_secs(self.config.network.connect_timeout))
+                        .build()?
+                );
+            }
+            Ok(self.client.as_ref().unwrap())
+        }
+
+        /// Makes a call to the configured server using the provided endpoint
+        async fn call(&mut self, endpoint: &str, payload: String, idempotency: Idempotency) -> AppResult<Response> {
+            let request: RequestBuilder = self.request(endpoint, payload)?;
+            self.send(request, idempotency).await
+        }
+
+        /// Makes a call to the configured server that only succeeds if the
+        /// registry is still at the given revision. Only an ETag is sent as a
+        /// precondition, as the server can't compute the hash of a registry.
+        /// Hash revisions are checked by `AppState::check_unchanged` right
+        /// before the call, which is why whole registry uploads need an ETag
+        async fn call_if_unchanged(&mut self, endpoint: &str, payload: String, revision: &Revision, idempotency: Idempotency) -> AppResult<Response> {
+            let request: RequestBuilder = match revision {
+                Revision::ETag(etag) => self.request(endpoint, payload)?.header("if-match", etag),
+                Revision::Hash(_) => self.request(endpoint, payload)?,
+            };
+            self.send(request, idempotency).await
+        }
+
+        /// Sends the given request, retrying it with an exponential backoff as
+        /// configured. Idempotent requests are retried after timeouts, broken
+        /// connections and temporary server errors, all other requests only if
+        /// they could not be sent at all
+        async fn send(&self, request: RequestBuilder, idempotency: Idempotency) -> AppResult<Response> {
+            let network: &NetworkSettings = &self.config.network;
+            let mut backoff: u64 = network.backoff;
+            let mut attempt: u32 = 0;
+            loop {
+                let Some(current) = request.try_clone() else {return Ok(request.send().await?)};
+                let retry: bool = attempt < network.retries;
+                let reason: String = match current.send().await {
+                    Ok(res) if retry
+                        && idempotency == Idempotency::Idempotent
+                        && matches!(res.status().as_u16(), 502..=504) => format!("HTTP status {}", res.status().as_u16()),
+                    Ok(res) => return Ok(res),
+                    Err(e) if retry
+                        && (e.is_connect() || (idempotency == Idempotency::Idempotent && (e.is_timeout() || e.is_request()))) => e.to_string(),
+                    Err(e) => return Err(e.into()),
+                };
+                if !self.quiet {
+                    println!("The request failed ({}), trying again in {}ms...", reason, backoff);
+                }
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                backoff = (backoff * 2).min(network.max_backoff);
+                attempt += 1;
+            }
+        }
+
+        /// Prepares a call to the configured server using the provided endpoint
+        fn request(&mut self, endpoint: &str, payload: String) -> AppResult<RequestBuilder> {
+            let user: HeaderValue = HeaderValue::from_str(&self.config.username)
+                .map_err(|_| AppError::Config("the username contains invalid characters".to_string()))?;
+            let secret: HeaderValue = HeaderValue::from_str(&self.config.secret)
+                .map_err(|_| AppError::Config("the secret contains invalid characters".to_string()))?;
+            let url: String = format!("{}{}", self.config.server_address, endpoint);
+            let auth_header: String = format!("{}", &self.config.auth_method).to_lowercase();
+
+            let request: RequestBuilder = self.handle_empty_client()?
+                .post(url)
+                .header(
+                    "user".to_string(),
+                    user
+                )
+                .header(
+                    auth_header,
+                    secret
+                )
+                .header(
+                    "content-type".to_string(),
+                    "text/xml".to_string(),
+                )
+                .body(payload);
+
+            Ok(request)
+        }
+    }
+
+    impl Backend for HttpBackend {
+        async fn fetch(&mut self) -> AppResult<(String, Option<String>)> {
+            let res: Response = self.call("/xml/fetch", "".to_string(), Idempotency::Idempotent).await?;
+            check_status(&res)?;
+            check_xml_content(&res)?;
+
+            let etag: Option<String> = res
+                .headers()
+                .get("etag")
+                .and_then(|e| e.to_str().ok())
+                .map(|e| e.to_string());
+            let txt = res.text().await?;
+            Ok((txt, etag))
+        }
+
+        async fn update(&mut self, registry: String, revision: &Revision) -> AppResult<()> {
+            // Sending the same registry twice leaves the server in the same state
+            let res: Response = self.call_if_unchanged("/xml/update", registry, revision, Idempotency::Idempotent).await?;
+            check_status(&res)
+        }
+
+        async fn apply(&mut self, changes: &ChangeSet, revision: &Revision) -> AppResult<bool> {
+            let res: Response = self.call_if_unchanged("/xml/changes", changes.to_xml(), revision, Idempotency::NonIdempotent).await?;
+            match res.status().as_u16() {
+                404 | 405 | 501 => Ok(false),
+                _ => check_status(&res).map(|_| true),
+            }
+        }
+
+        async fn get_by_id(&mut self, id: u16) -> AppResult<Option<String>> {
+            let res: Response = self.call(&format!("/xml/get_by_id/{}", id), "".to_string(), Idempotency::Idempotent).await?;
+            if res.status().as_u16() == 404 {
+                return Ok(None);
+            }
+            check_status(&res)?;
+            check_xml_content(&res)?;
+            Ok(Some(res.text().await?))
+        }
+
+        async fn add(&mut self, entry: String) -> AppResult<bool> {
+            let res: Response = self.call("/xml/add", entry, Idempotency::NonIdempotent).await?;
+            check_auth(&res)?;
+            Ok(res.status().is_success())
+        }
+
+        async fn edit(&mut self, id: u16, entry: String) -> AppResult<bool> {
+            let res: Response = self.call(&format!("/xml/edit/{}", id), entry, Idempotency::Idempotent).await?;
+            check_auth(&res)?;
+            Ok(res.status().is_success())
+        }
+
+        async fn delete(&mut self, id: u16) -> AppResult<bool> {
+            let res: Response = self.call(&format!("/xml/delete/{}", id), "".to_string(), Idempotency::Idempotent).await?;
+            check_auth(&res)?;
+            if res.status().as_u16() != 404 {
+                return Ok(res.status().is_success());
+            }
+            // A server without the route answers the same way, so the element
+            // only counts as deleted if it is gone from the registry
+            let (registry, _) = self.fetch().await?;
+            Ok(!from_str::<Registry>(&registry)?.entries.iter().any(|e| e.id == Some(id)))
+        }
+    }
+
+    /// A registry that is changed entry by entry, the way the server does it.
+    /// The entries are kept as XML, so fields unknown to the app are kept
+    #[derive(Default)]
+    struct LocalRegistry {
+        entries: Vec<(u16, String)>,
+    }
+
+    impl LocalRegistry {
+        fn parse(xml: &str) -> AppResult<Self> {
+            if xml.trim().is_empty() {
+                return Ok(Self::default());
+            }
+            let ids: Vec<u16> = from_str::<Registry>(xml)?
+                .entries
+                .iter()
+                .filter_map(|e| e.id)
+                .collect();
+            Ok(Self {
+                entries: entry_fragments(xml, &ids)?,
+            })
+        }
+
+        fn to_xml(&self) -> String {
+            let entries: String = self.entries.iter().map(|e| e.1.as_str()).collect();
+            format!("<registry>{}</registry>", entries)
+        }
+
+        fn revision(&self) -> String {
+            content_hash(&self.to_xml())
+        }
+
+        /// Fails if the registry is not at the given revision anymore
+        fn check(&self, revision: &Revision) -> AppResult<()> {
+            match revision {
+                Revision::ETag(etag) if *etag != self.revision() => Err(AppError::RegistryChanged),
+                _ => Ok(()),
+            }
+        }
+
+        /// Creates or replaces the given entry
+        fn put(&mut self, id: u16, entry: &str) {
+            match self.entries.iter_mut().find(|e| e.0 == id) {
+                Some(existing) => existing.1 = entry.to_string(),
+                None => self.entries.push((id, entry.to_string())),
+            }
+        }
+
+        fn apply(&mut self, changes: &ChangeSet) {
+            changes.created
+                .iter()
+                .chain(changes.modified.iter())
+                .for_each(|e| self.put(e.0, &e.1));
+            self.entries.retain(|e| !changes.deleted.contains(&e.0));
+        }
+
+        fn get(&self, id: u16) -> Option<String> {
+            self.entries.iter().find(|e| e.0 == id).map(|e| e.1.clone())
+        }
+
+        fn add(&mut self, entry: String) -> AppResult<bool> {
+            let Some(id) = from_str::<AppElement>(&entry)?.id else {return Ok(false)};
+            if self.entries.iter().any(|e| e.0 == id) {
+                return Ok(false);
+            }
+            self.entries.push((id, entry));
+            Ok(true)
+        }
+
+        fn edit(&mut self, id: u16, entry: String) -> bool {
+            let Some(existing) = self.entries.iter_mut().find(|e| e.0 == id) else {return false};
+            existing.1 = entry;
+            true
+        }
+
+        fn delete(&mut self, id: u16) {
+            self.entries.retain(|e| e.0 != id);
+        }
+    }
+
+    /// Keeps the registry in memory only, it is gone when the app stops
+    #[derive(Default)]
+    struct MemoryBackend {
+        registry: LocalRegistry,
+    }
+
+    impl Backend for MemoryBackend {
+        async fn fetch(&mut self) -> AppResult<(String, Option<String>)> {
+            Ok((self.registry.to_xml(), Some(self.registry.revision())))
+        }
+
+        async fn update(&mut self, registry: String, revision: &Revision) -> AppResult<()> {
+            self.registry.check(revision)?;
+            self.registry = LocalRegistry::parse(&registry)?;
+            Ok(())
+        }
+
+        async fn apply(&mut self, changes: &ChangeSet, revision: &Revision) -> AppResult<bool> {
+            self.registry.check(revision)?;
+            self.registry.apply(changes);
+            Ok(true)
+        }
+
+        async fn get_by_id(&mut self, id: u16) -> AppResult<Option<String>> {
+            Ok(self.registry.get(id))
+        }
+
+        async fn add(&mut self, entry: String) -> AppResult<bool> {
+            self.registry.add(entry)
+        }
+
+        async fn edit(&mut self, id: u16, entry: String) -> AppResult<bool> {
+            Ok(self.registry.edit(id, entry))
+        }
+
+        async fn delete(&mut self, id: u16) -> AppResult<bool> {
+            self.registry.delete(id);
+            Ok(true)
+        }
+    }
+
+    /// How often taking the lock of a registry file is tried, waiting
+    /// `LOCK_WAIT` milliseconds in between
+    const LOCK_ATTEMPTS: u32 = 50;
+    const LOCK_WAIT: u64 = 100;
+    /// Seconds after which a lock is considered left behind by a crashed process
+    const LOCK_STALE: u64 = 30;
+
+    /// Holds the lock of a registry file, the lock is released when dropped
+    struct FileLock {
+        path: PathBuf,
+    }
+
+    impl FileLock {
+        /// Takes the lock of the given file by creating a lock file next to it.
+        /// Waits while another process holds the lock, lock files of crashed
+        /// processes are removed
+        async fn take(file: &Path) -> AppResult<Self> {
+            let path: PathBuf = with_suffix(file, ".lock");
+            for _ in 0..LOCK_ATTEMPTS {
+                match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(_) => return Ok(Self {path}),
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        let stale: bool = fs::metadata(&path)
+                            .and_then(|e| e.modified())
+                            .ok()
+                            .and_then(|e| SystemTime::now().duration_since(e).ok())
+                            .map(|e| e.as_secs() >= LOCK_STALE)
+                            .unwrap_or(false);
+                        if stale {
+                            fs::remove_file(&path).ok();
+                            continue;
+                        }
+                    },
+                    Err(e) => return Err(AppError::Storage(e)),
+                }
+                tokio::time::sleep(Duration::from_millis(LOCK_WAIT)).await;
+            }
+            Err(AppError::Locked(file.display().to_string()))
+        }
+    }
+
+    impl Drop for FileLock {
+        fn drop(&mut self) {
+            fs::remove_file(&self.path).ok();
+        }
+    }
+
+    /// Keeps the registry in a XML file on disk, no server needed.
+    /// The file is locked while it is changed and replaced atomically
+    struct FileBackend {
+        path: PathBuf,
+    }
+
+    impl FileBackend {
+        /// Reads the registry, a missing file is an empty registry
+        fn read(&self) -> AppResult<LocalRegistry> {
+            match fs::read_to_string(&self.path) {
+                Ok(xml) => LocalRegistry::parse(&xml),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(LocalRegistry::default()),
+                Err(e) => Err(AppError::Storage(e)),
+            }
+        }
+
+        fn write(&self, registry: &LocalRegistry) -> AppResult<()> {
+            write_atomically(&self.path, &registry.to_xml()).map_err(AppError::Storage)
+        }
+
+        /// Reads the registry, changes it with the given function and writes it
+        /// back, holding the lock of the file the whole time
+        async fn change<T>(&self, change: impl FnOnce(&mut LocalRegistry) -> AppResult<T>) -> AppResult<T> {
+            let _lock: FileLock = FileLock::take(&self.path).await?;
+            let mut registry: LocalRegistry = self.read()?;
+            let result: T = change(&mut registry)?;
+            self.write(&registry)?;
+            Ok(result)
+        }
+    }
+
+    impl Backend for FileBackend {
+        async fn fetch(&mut self) -> AppResult<(String, Option<String>)> {
+            let registry: LocalRegistry = self.read()?;
+            Ok((registry.to_xml(), Some(registry.revision())))
+        }
+
+        async fn update(&mut self, registry: String, revision: &Revision) -> AppResult<()> {
+            self.change(|current| {
+                current.check(revision)?;
+                *current = LocalRegistry::parse(&registry)?;
+                Ok(())
+            }).await
+        }
+
+        async fn apply(&mut self, changes: &ChangeSet, revision: &Revision) -> AppResult<bool> {
+            self.change(|current| {
+                current.check(revision)?;
+                current.apply(changes);
+                Ok(true)
+            }).await
+        }
+
+        async fn get_by_id(&mut self, id: u16) -> AppResult<Option<String>> {
+            Ok(self.read()?.get(id))
+        }
+
+        async fn add(&mut self, entry: String) -> AppResult<bool> {
+            self.change(|current| current.add(entry)).await
+        }
+
+        async fn edit(&mut self, id: u16, entry: String) -> AppResult<bool> {
+            self.change(|current| Ok(current.edit(id, entry))).await
+        }
+
+        async fn delete(&mut self, id: u16) -> AppResult<bool> {
+            self.change(|current| {
+                current.delete(id);
+                Ok(true)
+            }).await
+        }
+    }
+
+    /// The backend the configured server address points to: `memory://` keeps
+    /// the registry in memory, `file://` in the given file and everything else
+    /// is the address of a Freemind server. File paths starting with `~/` are
+    /// relative to the home directory
+    enum Storage {
+        Http(HttpBackend),
+        Memory(MemoryBackend),
+        File(FileBackend),
+    }
+
+    impl Storage {
+        fn new(config: &AppConfig) -> Self {
+            let address: &str = config.server_address.as_str();
+            if address.starts_with("memory://") {
+                Self::Memory(MemoryBackend::default())
+            } else if let Some(path) = address.strip_prefix("file://") {
+                let path: PathBuf = match path.strip_prefix("~/") {
+                    Some(path) => dirs::home_dir().unwrap_or_default().join(path),
+                    None => PathBuf::from(path),
+                };
+                Self::File(FileBackend {
+                    path,
+                })
+            } else {
+                Self::Http(HttpBackend::new(config))
+            }
+        }
+
+        fn set_quiet(&mut self, quiet: bool) {
+            if let Self::Http(backend) = self {
+                backend.quiet = quiet;
+            }
+        }
+    }
+
+    impl Backend for Storage {
+        async fn fetch(&mut self) -> AppResult<(String, Option<String>)> {
+            match self {
+                Self::Http(backend) => backend.fetch().await,
+                Self::Memory(backend) => backend.fetch().await,
+                Self::File(backend) => backend.fetch().await,
+            }
+        }
+
+        async fn update(&mut self, registry: String, revision: &Revision) -> AppResult<()> {
+            match self {
+                Self::Http(backend) => backend.update(registry, revision).await,
+                Self::Memory(backend) => backend.update(registry, revision).await,
+                Self::File(backend) => backend.update(registry, revision).await,
+            }
+        }
+
+        async fn apply(&mut self, changes: &ChangeSet, revision: &Revision) -> AppResult<bool> {
+            match self {
+                Self::Http(backend) => backend.apply(changes, revision).await,
+                Self::Memory(backend) => backend.apply(changes, revision).await,
+                Self::File(backend) => backend.apply(changes, revision).await,
+            }
+        }
+
+        async fn get_by_id(&mut self, id: u16) -> AppResult<Option<String>> {
+            match self {
+                Self::Http(backend) => backend.get_by_id(id).await,
+                Self::Memory(backend) => backend.get_by_id(id).await,
+                Self::File(backend) => backend.get_by_id(id).await,
+            }
+        }
+
+        async fn add(&mut self, entry: String) -> AppResult<bool> {
+            match self {
+                Self::Http(backend) => backend.add(entry).await,
+                Self::Memory(backend) => backend.add(entry).await,
+                Self::File(backend) => backend.add(entry).await,
+            }
+        }
+
+        async fn edit(&mut self, id: u16, entry: String) -> AppResult<bool> {
+            match self {
+                Self::Http(backend) => backend.edit(id, entry).await,
+                Self::Memory(backend) => backend.edit(id, entry).await,
+                Self::File(backend) => backend.edit(id, entry).await,
+            }
+        }
+
+        async fn delete(&mut self, id: u16) -> AppResult<bool> {
+            match self {
+                Self::Http(backend) => backend.delete(id).await,
+                Self::Memory(backend) => backend.delete(id).await,
+                Self::File(backend) => backend.delete(id).await,
+            }
+        }
+    }
+
+    /// The current state of the app
+    pub struct AppState {
+        config: AppConfig,
+        backend: Storage,
+        elements: Vec<AppElement>,
+        /// The elements as they were on the server after the last sync
+        base: Vec<AppElement>,
+        synced: bool,
+        last_sync: Option<i64>,
+        registry_hash: Option<String>,
+        freshness: Freshness,
+        /// Whether the server accepts change sets instead of the whole registry
+        delta_upload: Option<bool>,
+        /// The last handle given to an element, handles are never given out twice
+        last_handle: u32,
+        journal: Option<Journal>,
+        /// Whether progress messages are held back, e.g. while syncing in the background
+        quiet: bool,
+    }
+
+    impl AppState {
+        pub fn new(config: AppConfig) -> Self {
+            Self {
+                backend: Storage::new(&config),
+                config,
+                elements: Vec::new(),
+                base: Vec::new(),
+                synced: false,
+                last_sync: None,
+                registry_hash: None,
+                freshness: Freshness::Unknown,
+                delta_upload: None,
+                last_handle: 0,
+                journal: None,
+                quiet: false,
+            }
+        }
+
+        /// Takes over the elements of the given cache, if it belongs to the
+        /// configured server and user. Returns whether the cache was used
+        pub fn load_cache(&mut self, cache: AppCache) -> bool {
+            if cache.server_address != self.config.server_address || cache.username != self.config.username {
+                return false;
+            }
+            self.elements = cache.elements
+                .into_iter()
+                .map(|e| {
+                    let mut element: AppElement = e.element;
+                    element.removed = e.removed;
+                    element.modified = e.modified;
+                    element.handle = e.handle;
+                    element.parent_handle = e.parent_handle;
+                    element
+                })
+                .collect();
+            self.last_handle = cache.last_handle;
+            while let Some(pos) = self.elements.iter().position(|e| e.handle == 0) {
+                self.elements[pos].handle = self.next_handle();
+            }
+            self.link_parents();
+            self.base = cache.base;
+            self.synced = !self.elements.iter().any(|e| e.removed || e.modified || e.id.is_none());
+            self.last_sync = cache.last_sync;
+            self.registry_hash = cache.registry_hash;
+            self.freshness = Freshness::Unknown;
+            self.delta_upload = cache.delta_upload;
+            true
+        }
+
+        /// Returns the current state in the form it is persisted in
+        pub fn to_cache(&self) -> AppCache {
+            AppCache {
+                server_address: self.config.server_address.clone(),
+                username: self.config.username.clone(),
+                last_sync: self.last_sync,
+                registry_hash: self.registry_hash.clone(),
+                delta_upload: self.delta_upload,
+                last_handle: self.last_handle,
+                elements: self.elements
+                    .iter()
+                    .map(|e| CachedElement {
+                        removed: e.removed,
+                        modified: e.modified,
+                        handle: e.handle,
+                        parent_handle: e.parent_handle,
+                        element: e.clone(),
+                    })
+                    .collect(),
+                base: self.base.clone(),
+            }
+        }
+
+        /// Holds back all progress messages while set, errors are still returned
+        pub fn set_quiet(&mut self, quiet: bool) {
+            self.quiet = quiet;
+            self.backend.set_quiet(quiet);
+        }
+
+        /// Prints the given progress message, unless the state is quiet
+        fn progress(&self, message: impl fmt::Display) {
+            if !self.quiet {
+                println!("{}", message);
+            }
+        }
+
+        pub fn config(&self) -> &AppConfig {
+            &self.config
+        }
+
+        /// Replaces the config of the running state. If the new config points to
+        /// another server or user, the local elements are dropped as they belong
+        /// to the previous registry
+        pub fn set_config(&mut self, config: AppConfig) {
+            let same_registry: bool = config.server_address == self.config.server_address
+                && config.username == self.config.username;
+            self.config = config;
+            self.backend = Storage::new(&self.config);
+            self.backend.set_quiet(self.quiet);
+            if !same_registry {
+                self.elements.clear();
+                self.base.clear();
+                self.synced = false;
+                self.last_sync = None;
+                self.registry_hash = None;
+                self.freshness = Freshness::Unknown;
+                self.delta_upload = None;
+                if let Some(journal) = self.journal.as_mut() {
+                    journal.file.server_address = self.config.server_address.clone();
+                    journal.file.username = self.config.username.clone();
+                    journal.clear().ok();
+                }
+            }
+        }
+
+        /// Writes all further operations to the given journal before they are
+        /// sent. Operations the journal still holds from an earlier run are
+        /// applied to the local elements again, so the next sync sends whatever
+        /// the server did not get yet. Returns the number of replayed operations
+        pub fn attach_journal(&mut self, mut journal: Journal) -> usize {
+            let same_registry: bool = journal.file.server_address == self.config.server_address
+                && journal.file.username == self.config.username;
+            let mut entries: Vec<JournalEntry> = std::mem::take(&mut journal.file.entries);
+            if !same_registry {
+                // Operations on another registry can't be replayed here
+                entries.clear();
+                journal.file.server_address = self.config.server_address.clone();
+                journal.file.username = self.config.username.clone();
             }
+            entries.sort_by_key(|e| e.seq);
+            let replayed: usize = entries.len();
+            for entry in entries.iter() {
+                self.replay(entry);
+            }
+            if replayed != 0 {
+                self.synced = false;
+                self.link_parents();
+            }
+            journal.file.entries = entries;
+            self.journal = Some(journal);
+            replayed
         }
-    }
 
-    /// The current state of the app
-    pub struct AppState {
-        config: AppConfig,
-        client: Option<Client>,
-        elements: Vec<AppElement>,
-        synced: bool,
-    }
+        /// Applies a journaled operation to the local elements. Applying the
+        /// same operation twice leaves the elements as applying it once.
+        /// Operations are matched to the local elements by ID, or by UUID as
+        /// every attempt to create an element generates another ID
+        fn replay(&mut self, entry: &JournalEntry) {
+            let journaled: &AppElement = &entry.entry;
+            let Some(id) = journaled.id else {return};
+            let known: Option<usize> = self.elements
+                .iter()
+                .position(|e| e.id == Some(id))
+                .or_else(|| self.elements.iter().position(|e| e.uuid.is_some() && e.uuid == journaled.uuid));
+            let pos: usize = match known {
+                Some(pos) => pos,
+                None => {
+                    let mut element: AppElement = journaled.clone();
+                    if entry.kind == Operation::Create && element.uuid.is_some() {
+                        // Whether the server got the element is found out by
+                        // its UUID with the next sync
+                        element.id = None;
+                    } else if entry.kind == Operation::Create {
+                        // The unsynced element of an older client the ID was generated for
+                        self.elements.retain(|e| e.id.is_some() || !MergeField::ALL.iter().all(|f| e.field_eq(journaled, *f)));
+                    }
+                    self.insert(element);
+                    self.elements.len() - 1
+                },
+            };
+            let element: &mut AppElement = &mut self.elements[pos];
+            match entry.kind {
+                Operation::Create | Operation::Edit => {
+                    MergeField::ALL.iter().for_each(|f| element.take_field(journaled, *f));
+                    element.removed = false;
+                    element.modified = true;
+                },
+                Operation::Delete => {
+                    // Deleted by ID, in case the server got the element
+                    element.id = element.id.or(Some(id));
+                    element.removed = true;
+                },
+            }
+        }
 
-    impl AppState {
-        pub fn new(config: AppConfig) -> Self {
-            Self {
-                config,
-                client: None,
-                elements: Vec::new(),
-                synced: false,
+        /// Writes the given operations to the journal, if there is one
+        fn journal_record(&mut self, operations: Vec<(Operation, AppElement)>) -> AppResult<()> {
+            match self.journal.as_mut() {
+                Some(journal) => journal.record(operations),
+                None => Ok(()),
             }
         }
 
+        /// Drops the operations on the given element from the journal. Failing
+        /// to do so is no error, as replaying confirmed operations is harmless
+        fn journal_confirm(&mut self, id: u16) {
+            if let Some(Err(e)) = self.journal.as_mut().map(|e| e.confirm(id)) {
+                self.progress(e);
+            }
+        }
+
+        /// Checks whether the server accepts the configured credentials
+        pub async fn test_connection(&mut self) -> AppResult<()> {
+            self.fetch().await?;
+            Ok(())
+        }
+
+        /// Returns the unix timestamp of the last successful sync
+        pub fn last_sync(&self) -> Option<i64> {
+            self.last_sync
+        }
+
+        /// Asks the server whether the registry changed since the last sync
+        /// and marks the local state as fresh or stale accordingly
+        pub async fn check_freshness(&mut self) -> AppResult<Freshness> {
+            let result: String = self.fetch().await?;
+            self.freshness = match from_str::<Registry>(&result) {
+                Ok(remote) if Some(registry_hash(&remote.entries)) == self.registry_hash => Freshness::Fresh,
+                _ => Freshness::Stale,
+            };
+            Ok(self.freshness)
+        }
+
         pub fn get_elements(&self) -> &Vec<AppElement> {
-            return &self.elements;
+            &self.elements
         }
 
         pub fn get_element_by_id(&mut self, id: u16) -> Option<&mut AppElement> {
@@ -288,37 +2404,77 @@ pub(crate) mod data_types {
                 .find(|e| e.id == Some(id))
         }
 
-        pub fn get_ids(&self, ignore_removed: bool) -> Vec<u16> {
-            return self.elements
-                .clone()
-                .into_iter()
-                .filter(|e| !e.removed && ignore_removed)
-                .filter_map(|e| e.id)
-                .collect();
+        pub fn get_element(&mut self, handle: u32) -> Option<&mut AppElement> {
+            self
+                .elements
+                .iter_mut()
+                .find(|e| e.handle == handle)
+        }
+
+        /// Returns the handle of the element the user refers to by the given
+        /// label, either its ID or e.g. 'new1' for an element that was not synced
+        /// when it was listed. Elements keep their handle when they get an ID
+        pub fn find_handle(&self, label: &str) -> Option<u32> {
+            let label: &str = label.trim();
+            let element: Option<&AppElement> = match label.strip_prefix(NEW_LABEL) {
+                Some(handle) => {
+                    let handle: u32 = handle.parse().ok()?;
+                    self.elements.iter().find(|e| e.handle == handle)
+                },
+                None => {
+                    let id: u16 = label.parse().ok()?;
+                    self.elements.iter().find(|e| e.id == Some(id))
+                },
+            };
+            element.map(|e| e.handle)
+        }
 
+        /// Returns the handles and labels of the elements that are not removed
+        /// and match the filter, for the user to select from
+        pub fn get_labels(&self, filter: impl Fn(&AppElement) -> bool) -> Vec<(u32, String)> {
+            self.elements
+                .iter()
+                .filter(|e| !e.removed && filter(e))
+                .map(|e| (e.handle, e.label()))
+                .collect()
         }
 
         pub fn push(&mut self, element: Option<AppElement>) {
             if let Some(e) = element {
-                self.elements.push(e)
+                self.insert(e)
             }
         }
 
+        /// Adds the element to the local state with a new handle
+        fn insert(&mut self, mut element: AppElement) {
+            element.handle = self.next_handle();
+            self.elements.push(element);
+        }
+
+        /// Returns a handle no element was given before, so the label of a
+        /// dropped element never points to another one
+        fn next_handle(&mut self) -> u32 {
+            let last: u32 = self.elements.iter().map(|e| e.handle).max().unwrap_or(0);
+            self.last_handle = self.last_handle.max(last) + 1;
+            self.last_handle
+        }
+
         pub fn unsynced(&mut self) {
             self.synced = false;
         }
 
+        /// Sorts the elements by their due date, events by their start
         pub fn sort_by_due(&mut self) {
             self.elements.sort_by(|a, b| {
-                match a.due {
+                match a.when() {
                     Some(due_a) => {
-                        match b.due {
+                        match b.when() {
                             Some(due_b) => {due_a.cmp(&due_b)},
                             None => {due_a.cmp(&0)}
                         }
                     },
                     None => {
-                        match b.due {
+                        match b.when() {
                             Some(due_b) => {due_b.cmp(&0)},
                             None => {0.cmp(&0)}
                         }
@@ -327,6 +2483,96 @@ pub(crate) mod data_types {
             })
         }
 
+        /// Keeps the links between subtasks and their parents valid. Links to
+        /// parents within the local state take over the parent's current ID,
+        /// other links are found by the ID of the parent.
+        /// Elements on the server are marked as modified if their parent ID changed
+        fn link_parents(&mut self) {
+            for pos in 0..self.elements.len() {
+                let parent_handle: u32 = self.elements[pos].parent_handle;
+                let linked: Option<&AppElement> = self.elements.iter().find(|e| parent_handle != 0 && e.handle == parent_handle);
+                if let Some(parent_id) = linked.map(|e| e.id) {
+                    let element: &mut AppElement = &mut self.elements[pos];
+                    if element.parent != parent_id {
+                        element.parent = parent_id;
+                        element.modified |= element.id.is_some();
+                    }
+                    continue;
+                }
+                let parent: Option<u16> = self.elements[pos].parent;
+                self.elements[pos].parent_handle = self.elements
+                    .iter()
+                    .find(|e| parent.is_some() && e.id == parent)
+                    .map(|e| e.handle)
+                    .unwrap_or(0);
+            }
+        }
+
+        /// Makes the element a subtask of the given parent, or a top level
+        /// element. Returns false if the parent is unknown or a subtask of the element
+        pub fn set_parent(&mut self, handle: u32, parent: Option<u32>) -> bool {
+            if let Some(parent) = parent {
+                if !self.elements.iter().any(|e| e.handle == parent) || self.is_descendant(parent, handle) {
+                    return false;
+                }
+            }
+            let parent_id: Option<u16> = self.elements.iter().find(|e| Some(e.handle) == parent).and_then(|e| e.id);
+            let Some(element) = self.get_element(handle) else {return false};
+            if element.parent_handle != parent.unwrap_or(0) || element.parent != parent_id {
+                element.parent_handle = parent.unwrap_or(0);
+                element.parent = parent_id;
+                element.modified = true;
+                self.synced = false;
+            }
+            true
+        }
+
+        /// Returns whether the element is the given ancestor or one of its subtasks
+        fn is_descendant(&self, handle: u32, ancestor: u32) -> bool {
+            let mut current: u32 = handle;
+            // Bounded, in case the links of the server form a cycle
+            for _ in 0..=self.elements.len() {
+                if current == ancestor {
+                    return true;
+                }
+                match self.elements.iter().find(|e| e.handle == current) {
+                    Some(element) if element.parent_handle != 0 => current = element.parent_handle,
+                    _ => return false,
+                }
+            }
+            false
+        }
+
+        /// Returns the handles of all subtasks of the element, their subtasks
+        /// included, that are not removed
+        pub fn subtasks(&self, handle: u32) -> Vec<u32> {
+            self.elements
+                .iter()
+                .filter(|e| !e.removed && e.handle != handle && self.is_descendant(e.handle, handle))
+                .map(|e| e.handle)
+                .collect()
+        }
+
+        /// Returns how many of the subtasks of the element are completed and how
+        /// many there are, or None if it has no subtasks
+        pub fn subtask_progress(&self, handle: u32) -> Option<(usize, usize)> {
+            let subtasks: Vec<u32> = self.subtasks(handle);
+            if subtasks.is_empty() {
+                return None;
+            }
+            let done: usize = self.elements
+                .iter()
+                .filter(|e| subtasks.contains(&e.handle) && e.is_completed())
+                .count();
+            Some((done, subtasks.len()))
+        }
+
+        /// Sorts the elements from the highest to the lowest priority, elements
+        /// of the same priority keep their order
+        pub fn sort_by_priority(&mut self) {
+            self.elements.sort_by_key(|e| std::cmp::Reverse(e.priority))
+        }
+
         /// Returns a string that supposes to indicate whether modifications
         /// have been made to the local state
         pub fn modified_string(&self) -> String {
@@ -337,141 +2583,358 @@ pub(crate) mod data_types {
         }
 
 
-        /// Retreives the requested id directly from the server and returns a string
-        /// with the content:
-        pub async fn live_get_by_id(&mut self, id: u16) -> Result<String, reqwest::Error> {
-            self.handle_empty_client();
-            let mut result: String = String::new();
-            
-            let res: Response = self.call(&format!("/xml/get_by_id/{}", id), "".to_string()).await?;
-            let headers = res.headers();
-            
-            if headers.get("content-type") != Some(&HeaderValue::from_static("text/xml")) {
-                return Ok(result);
-            }
-            
-            let xml: String = res.text().await?;
+        /// Retreives the requested id directly from the server, returns None
+        /// if the server does not know the element
+        pub async fn live_get_by_id(&mut self, id: u16) -> AppResult<Option<AppElement>> {
+            let Some(xml) = self.backend.get_by_id(id).await? else {return Ok(None)};
 
-            //println!("{}", xml);
-            let mut reader = Reader::from_str(&xml);
-            
-            reader.trim_text(true);
+            // The entry may either come on its own or wrapped in a registry
+            let element: Option<AppElement> = match from_str::<Registry>(&xml) {
+                Ok(registry) => registry.entries.into_iter().find(|e| e.id == Some(id)),
+                Err(_) => from_str::<AppElement>(&xml).ok(),
+            };
+            Ok(element)
+        }
 
-            let mut enabled: bool = false;
-            let mut indentation: usize = 0;
+        /// Retreives all elements directly from the server, without touching
+        /// the local state
+        pub async fn live_list(&mut self) -> AppResult<Vec<AppElement>> {
+            let result: String = self.fetch().await?;
+            let mut elements: Vec<AppElement> = from_str::<Registry>(&result)?.entries;
+            elements.sort_by_key(|e| e.when().unwrap_or(0));
+            Ok(elements)
+        }
 
-            loop {
-                match reader.read_event() {
-                    Ok(Event::Start(e)) if e.name().as_ref() == b"entry" => {
-                        // We expect just one entry here so it is fine to do it this way
-                        enabled = true;
-                    },
-                    Ok(Event::End(e)) if e.name().as_ref() == b"entry" => {
-                        enabled = false;
-                    },
-                    Ok(Event::Start(e)) if enabled => {
-                        result.push_str("\n");
-                        result.push_str(&" ".repeat(indentation));
-                        result.push_str(str::from_utf8(e.name().as_ref()).unwrap_or(""));
-                        result.push_str(": ");
-                        indentation += 1;
-                    }
-                    Ok(Event::Text(txt)) if enabled => {
-                        result.push_str(
-                            &txt.unescape().unwrap()
-                        );
-                    }
-                    Ok(Event::End(_)) if enabled => {
-                        indentation -= 1;
-                    }
-                    Ok(Event::Eof) => break,
-                    Err(_) => break,
-                    _ => ()
-                }
+        /// Creates the given element directly on the server, generating an ID
+        /// that is neither known locally nor taken on the server.
+        /// Returns the ID if the server accepted the element
+        pub async fn live_add(&mut self, element: &mut AppElement) -> AppResult<Option<u16>> {
+            let existing_ids: Vec<u16> = self.server_ids().await?;
+            let id: u16 = self.assign_new_id(element, &existing_ids)?;
+            Ok(self.send_add(element).await?.then_some(id))
+        }
+
+        /// Returns the IDs of all entries on the server, including the ones other
+        /// clients created since the last sync
+        async fn server_ids(&mut self) -> AppResult<Vec<u16>> {
+            let result: String = self.fetch().await?;
+            Ok(from_str::<Registry>(&result)?.entries.into_iter().filter_map(|e| e.id).collect())
+        }
+
+        /// Gives the element an ID that is neither known locally nor in the
+        /// given list of existing IDs
+        fn assign_new_id(&self, element: &mut AppElement, existing_ids: &[u16]) -> AppResult<u16> {
+            let mut existing_ids: Vec<u16> = self.elements
+                .iter()
+                .chain(self.base.iter())
+                .filter_map(|e| e.id)
+                .chain(existing_ids.iter().copied())
+                .collect();
+            element.generate_id(&mut existing_ids).ok_or(AppError::NoFreeId)
+        }
+
+        /// Creates the given element on the server, returns whether the server
+        /// accepted it
+        async fn send_add(&mut self, element: &AppElement) -> AppResult<bool> {
+            self.backend.add(element.to_xml()).await
+        }
+
+        /// Replaces the element with the same ID directly on the server.
+        /// Returns whether the server accepted the change
+        pub async fn live_edit(&mut self, element: &AppElement) -> AppResult<bool> {
+            let Some(id) = element.id else {return Ok(false)};
+            self.backend.edit(id, element.to_xml()).await
+        }
+
+        /// Deletes the element with the given ID directly on the server.
+        /// Returns whether the element is gone from the server
+        pub async fn live_delete(&mut self, id: u16) -> AppResult<bool> {
+            self.backend.delete(id).await
+        }
+
+        /// Returns whether there are local changes that are not on the server yet
+        fn has_local_changes(&self) -> bool {
+            self.elements.iter().any(|e| e.removed || e.modified || e.id.is_none())
+        }
+
+        /// Sends the given new element to the server right away, without a full sync.
+        /// The element is kept locally as unsynced if the server can't be reached or
+        /// rejects it. Returns whether the server accepted the element
+        pub async fn direct_add(&mut self, mut element: AppElement) -> AppResult<bool> {
+            let existing_ids: Vec<u16> = match self.server_ids().await {
+                Ok(ids) => ids,
+                Err(e) => {
+                    self.insert(element);
+                    self.synced = false;
+                    return Err(e);
+                },
+            };
+            let id: u16 = self.assign_new_id(&mut element, &existing_ids)?;
+            self.journal_record(vec![(Operation::Create, element.clone())])?;
+            let result = self.send_add(&element).await;
+            if result.is_ok() {
+                self.journal_confirm(id);
+            }
+            let accepted: bool = matches!(result, Ok(true));
+            if accepted {
+                self.base.push(element.clone());
+            } else {
+                element.id = None;
             }
+            self.insert(element);
+            self.synced = !self.has_local_changes();
+            result.map(|_| accepted)
+        }
 
-            Ok(result)
+        /// Sends the local version of the given element to the server right away,
+        /// without a full sync. Returns whether the server accepted the change
+        pub async fn direct_edit(&mut self, id: u16) -> AppResult<bool> {
+            let Some(element) = self.get_element_by_id(id) else {return Ok(false)};
+            let element: AppElement = element.clone();
+
+            self.journal_record(vec![(Operation::Edit, element.clone())])?;
+            let accepted: bool = self.live_edit(&element).await?;
+            self.journal_confirm(id);
+            if accepted {
+                if let Some(element) = self.get_element_by_id(id) {
+                    element.modified = false;
+                    let element: AppElement = element.clone();
+                    self.base.retain(|e| e.id != Some(id));
+                    self.base.push(element);
+                }
+            }
+            self.synced = !self.has_local_changes();
+            Ok(accepted)
         }
 
-        fn handle_empty_client(&mut self) {
-            if self.client.is_none() {
-                self.client = Some(
-                    Client::builder()
-                        .user_agent("Freemind CLI")
-                        .build().unwrap()
-                );
+        /// Deletes the given element on the server right away, without a full sync.
+        /// Returns whether the element is gone from the server
+        pub async fn direct_remove(&mut self, id: u16) -> AppResult<bool> {
+            if let Some(element) = self.elements.iter().find(|e| e.id == Some(id)) {
+                self.journal_record(vec![(Operation::Delete, element.clone())])?;
+            }
+            let accepted: bool = self.live_delete(id).await?;
+            self.journal_confirm(id);
+            if accepted {
+                self.elements.retain(|e| e.id != Some(id));
+                self.base.retain(|e| e.id != Some(id));
             }
+            self.synced = !self.has_local_changes();
+            Ok(accepted)
         }
 
-        /// Adds non existing elements to the State of elements, skips
-        /// already existing elements
-        fn add_new_elements(&mut self, new: Vec<AppElement>) {
-            new.into_iter().for_each(|e| {
-                if self.elements.iter().any(|i| &e == i) {
+        /// Reconciles the local elements with the given registry from the server.
+        /// Elements that are unknown locally are added, unmodified local elements
+        /// are updated to the values of the server and elements that vanished
+        /// from the server are dropped. Elements that were created locally and
+        /// did not get an ID yet are kept
+        fn reconcile(&mut self, remote: Vec<AppElement>, report: &mut SyncReport) {
+            self.elements.retain(|e| {
+                let Some(id) = e.id else {return true};
+                if remote.iter().any(|r| r.same_entry(e)) {
+                    return true;
+                }
+                if !e.removed {
+                    report.deleted.push(id);
+                }
+                false
+            });
 
-                } else {
-                    self.elements.push(e)
+            remote.into_iter().for_each(|r| {
+                match self.elements.iter_mut().find(|e| e.same_entry(&r)) {
+                    Some(local) => {
+                        if local.modified || local.removed {
+                            return;
+                        }
+                        if local.uuid.is_none() {
+                            local.uuid = r.uuid.clone();
+                        }
+                        if !MergeField::ALL.iter().all(|f| local.field_eq(&r, *f)) {
+                            MergeField::ALL.iter().for_each(|f| local.take_field(&r, *f));
+                            report.updated.push(r.id.unwrap_or(0));
+                        }
+                    },
+                    None => {
+                        report.added.push(r.id.unwrap_or(0));
+                        self.insert(r);
+                    }
                 }
-            })
+            });
+            self.link_parents();
+        }
+
+        /// Locally edited elements that were deleted on the server in the meantime
+        /// lose their ID, so they are created again instead of losing the edit.
+        /// This includes elements whose ID was taken by another client's entry.
+        /// Locally removed elements whose entry is gone are forgotten, so the
+        /// entry of another client with the same ID is not deleted instead
+        fn revive_deleted_edits(&mut self, remote: &[AppElement], report: &mut SyncReport) {
+            self.elements.retain(|e| {
+                !e.removed || e.id.is_none() || remote.iter().any(|r| r.same_entry(e))
+            });
+            self.elements
+                .iter_mut()
+                .filter(|e| e.modified && !e.removed)
+                .filter(|e| e.id.is_some() && !remote.iter().any(|r| r.same_entry(e)))
+                .for_each(|e| {
+                    report.revived.push(e.id.unwrap_or(0));
+                    e.id = None;
+                    e.modified = false;
+                });
+        }
+
+        /// Elements without an ID whose entry is on the server already, as the
+        /// answer to their upload got lost, take over the ID of the entry with
+        /// their UUID instead of being created again
+        fn adopt_created_entries(&mut self, remote: &[AppElement]) {
+            for element in self.elements.iter_mut().filter(|e| e.id.is_none() && e.uuid.is_some()) {
+                let Some(theirs) = remote.iter().find(|r| r.uuid == element.uuid) else {continue};
+                element.id = theirs.id;
+                // Edits made after the lost upload are still to be sent
+                element.modified = !MergeField::ALL.iter().all(|f| element.field_eq(theirs, *f));
+            }
         }
 
         /// Generates IDs for all elements in the current state that don't already
         /// have one. Needs a full list of existing IDs to avoid during generation
-        fn add_missing_ids(&mut self, existing_ids: &mut Vec<u16>) -> (bool, Vec<u16>) {
+        fn add_missing_ids(&mut self, existing_ids: &mut Vec<u16>) -> AppResult<(bool, Vec<u16>)> {
             let mut new_ids: Vec<u16> = Vec::new();
-            let count_after: usize = self.elements
-                .iter_mut()
-                .filter(|e| e.id.is_none())
-                .map(|e| {
-                    new_ids.push(e.generate_id(existing_ids))
-                }).count();
-            (count_after != 0, new_ids)
+            for element in self.elements.iter_mut().filter(|e| e.id.is_none()) {
+                new_ids.push(element.generate_id(existing_ids).ok_or(AppError::NoFreeId)?);
+                // Local edits are part of the created entry
+                element.modified = false;
+            }
+            Ok((!new_ids.is_empty(), new_ids))
         }
 
-        /// Makes a call to the configured server using the provided endpoint
-        async fn call(&mut self, endpoint: &str, payload: String) -> Result<Response, reqwest::Error> {
-            self.handle_empty_client();
-            let res: Response = self.client.as_ref().unwrap()
-                .post(format!("{}{}", self.config.server_address, endpoint))
-                .header(
-                    "user".to_string(),
-                    HeaderValue::from_str(&self.config.username).unwrap()
-                )
-                .header(
-                    format!("{}", &self.config.auth_method).to_lowercase(),
-                    &self.config.secret
-                )
-                .header(
-                    "content-type".to_string(),
-                    "text/xml".to_string(),
-                )
-                .body(payload)
-                .send()
-                .await?;
+        /// Merges the fetched remote elements into the locally modified elements,
+        /// comparing every field against the state of the last sync.
+        /// Fields only changed on one side are taken from that side, fields changed
+        /// on both sides are handed to the sync handler.
+        /// Returns the number of fields that were taken from the server
+        fn merge_remote<H: SyncHandler>(&mut self, remote: &[AppElement], handler: &mut H) -> usize {
+            let mut merged: usize = 0;
+            for element in self.elements.iter_mut().filter(|e| e.modified && !e.removed) {
+                let Some(id) = element.id else {continue};
+                let Some(theirs) = remote.iter().find(|e| e.same_entry(element)) else {continue};
+                let base: &AppElement = self.base
+                    .iter()
+                    .find(|e| e.same_entry(element))
+                    .unwrap_or(theirs);
+
+                for field in MergeField::ALL {
+                    if element.field_eq(theirs, field) || theirs.field_eq(base, field) {
+                        // Same on both sides or only changed locally
+                        continue;
+                    }
+                    if element.field_eq(base, field) {
+                        // Only changed on the server
+                        element.take_field(theirs, field);
+                        merged += 1;
+                        continue;
+                    }
+                    let conflict: Conflict = Conflict {
+                        id,
+                        field,
+                        base: base.field_string(field),
+                        local: element.field_string(field),
+                        remote: theirs.field_string(field),
+                    };
+                    if handler.resolve(&conflict) == Resolution::Remote {
+                        element.take_field(theirs, field);
+                        merged += 1;
+                    }
+                }
 
-            Ok(res)
+                if MergeField::ALL.iter().all(|f| element.field_eq(theirs, *f)) {
+                    element.modified = false;
+                }
+            }
+            merged
         }
 
-        /// Fetches the whole registry from the server
-        async fn fetch(&mut self) -> Result<String, reqwest::Error> {
-            let res: Response = self.call("/xml/fetch", "".to_string()).await?;
-
-            let headers = res.headers();
-            if headers.get("content-type") == Some(&HeaderValue::from_static("text/xml")) {
-                let txt = res.text().await?;
-                return Ok(txt);
+        /// Lists the changes that uploading the local state would make to the
+        /// given remote elements
+        fn plan(&self, remote: &[AppElement]) -> SyncPlan {
+            let mut plan: SyncPlan = SyncPlan::default();
+            let empty: AppElement = AppElement::new(None, String::new(), String::new(), None, Vec::new());
+            for element in self.elements.iter() {
+                let theirs: Option<&AppElement> = remote.iter().find(|e| e.same_entry(element));
+                let fields: Vec<FieldChange> = MergeField::ALL
+                    .iter()
+                    .filter(|f| theirs.map(|t| !element.field_eq(t, **f)).unwrap_or(true))
+                    .map(|f| FieldChange {
+                        field: *f,
+                        before: theirs.unwrap_or(&empty).field_string(*f),
+                        after: if element.removed {String::new()} else {element.field_string(*f)},
+                    })
+                    .filter(|e| e.before != e.after)
+                    .collect();
+                let change = |fields: Vec<FieldChange>| PlannedChange {
+                    id: element.id,
+                    title: element.title(),
+                    fields,
+                };
+                match theirs {
+                    None if element.id.is_none() => plan.created.push(change(fields)),
+                    Some(_) if element.removed => plan.deleted.push(change(Vec::new())),
+                    Some(_) if element.modified && !fields.is_empty() => plan.modified.push(change(fields)),
+                    _ => (),
+                }
             }
+            plan
+        }
 
-            Ok(String::new())
+        /// Fetches the whole registry from the server
+        async fn fetch(&mut self) -> AppResult<String> {
+            Ok(self.fetch_with_etag().await?.0)
+        }
+
+        /// Fetches the whole registry from the server, together with the ETag
+        /// of the registry if the server sends one
+        async fn fetch_with_etag(&mut self) -> AppResult<(String, Option<String>)> {
+            self.backend.fetch().await
         }
 
-        /// Uploads the given payload to the server and returns the HTTP status code
-        async fn upload(&mut self, payload: String) -> Result<u16, reqwest::Error> {
-            let res: Response = self.call("/xml/update", payload).await?;
+        /// Fails if the registry on the server is not at the given revision anymore.
+        /// Servers that send an ETag check this themselves on upload, for all other
+        /// servers the registry is fetched again and compared by its hash.
+        /// This check and the upload are two requests, so only change sets, which
+        /// replace just the entries they touch, are sent after it
+        async fn check_unchanged(&mut self, revision: &Revision) -> AppResult<()> {
+            let Revision::Hash(hash) = revision else {return Ok(())};
+            let result: String = self.fetch().await?;
+            if &registry_hash(&from_str::<Registry>(&result)?.entries) != hash {
+                return Err(AppError::RegistryChanged);
+            }
+            Ok(())
+        }
 
-            let status = res.status().as_u16();
+        /// Uploads the given payload to the server, fails if the server did not
+        /// accept it
+        async fn upload(&mut self, payload: String, revision: &Revision) -> AppResult<()> {
+            self.backend.update(payload, revision).await
+        }
 
-            return Ok(status)
+        /// Sends only the given changes to the server if it supports change sets,
+        /// otherwise uploads the whole registry. Remembers whether the server
+        /// supports change sets, so it is only asked once.
+        /// The whole registry is only uploaded with an ETag as precondition,
+        /// without one it would overwrite the writes of other clients since the fetch
+        async fn upload_changes(&mut self, changes: ChangeSet, payload: String, revision: &Revision) -> AppResult<()> {
+            self.check_unchanged(revision).await?;
+            if self.delta_upload != Some(false) {
+                if self.backend.apply(&changes, revision).await? {
+                    self.delta_upload = Some(true);
+                    return Ok(());
+                }
+                self.progress("The server does not support change sets, uploading the whole registry...");
+                self.delta_upload = Some(false);
+            }
+            if matches!(revision, Revision::Hash(_)) {
+                return Err(AppError::UnprotectedUpload);
+            }
+            self.upload(payload, revision).await
         }
 
         /// Takes the whole XML Document and removes all Entries that were removed
@@ -497,7 +2960,6 @@ pub(crate) mod data_types {
                         let mut write = true;
                         e
                             .attributes()
-                            .into_iter()
                             .filter_map(|f| f.ok())
                             .for_each(|val| {
                                 if val.key.local_name().as_ref() == b"id" {
@@ -547,41 +3009,39 @@ pub(crate) mod data_types {
 
             Ok((
                 modified,
-                str::from_utf8(
+                String::from_utf8_lossy(
                     &writer.into_inner().into_inner()
                 )
-                .unwrap()
                 .to_string()
             ))
         }
 
         /// Takes the whole XML Document and inserts Entries defined by the ids vec into it
-        fn insert_created_entries(&self, xml: String, ids: Vec<u16>) -> String {
+        fn insert_created_entries(&self, xml: String, ids: Vec<u16>) -> Result<String, quick_xml::Error> {
             let mut reader = Reader::from_str(&xml);
             let mut writer = Writer::new(Cursor::new(Vec::new()));
 
             loop {
                 match reader.read_event() {
                     Ok(Event::Start(e)) if e.name().as_ref() == b"registry" => {
-                        writer.write_event(Event::Start(e.to_owned())).unwrap();
-                        self.elements
+                        writer.write_event(Event::Start(e.to_owned()))?;
+                        for element in self.elements
                             .iter()
-                            .filter(|e| ids.iter().any(|i| Some(i) == e.id.as_ref()))
-                            .map(|e| {
-                                e.write(&mut writer, true).unwrap();
-                            }).count();
+                            .filter(|e| ids.iter().any(|i| Some(i) == e.id.as_ref())) {
+                            element.write(&mut writer, true)?;
+                        }
                     },
                     Ok(Event::Eof) => break,
-                    Ok(e) => {writer.write_event(e).unwrap();}
+                    Ok(e) => {writer.write_event(e)?;}
                     Err(_) => break,
                 }
             }
 
-            str::from_utf8(
+            Ok(String::from_utf8_lossy(
                 &writer
                 .into_inner()
                 .into_inner()
-            ).unwrap().to_string()
+            ).to_string())
         }
 
         /// Takes the whole XML Document and edits Entries that are marked to be
@@ -600,7 +3060,7 @@ pub(crate) mod data_types {
                 match reader.read_event() {
                     Ok(Event::Start(e)) if change_this => {
                         match e.name().as_ref() {
-                            b"name" | b"description" | b"due" | b"tags" => {
+                            b"name" | b"description" | b"due" | b"start" | b"end" | b"allday" | b"completed" | b"priority" | b"recurrence" | b"parent" | b"tags" => {
                                 skip_subtag = e.to_owned();
                             },
                             _ => {
@@ -612,13 +3072,14 @@ pub(crate) mod data_types {
 
 
                     },
+                    // Empty fields like `<description/>` are written anew as well
+                    Ok(Event::Empty(e)) if change_this && skip_subtag == BytesStart::new("")
+                        && matches!(e.name().as_ref(), b"name" | b"description" | b"due" | b"start" | b"end" | b"allday" | b"completed" | b"priority" | b"recurrence" | b"parent" | b"tags") => (),
                     Ok(Event::Start(e)) if e.name().as_ref() == b"entry" => {
                         let mut write: bool = true;
-                        e
+                        for val in e
                             .attributes()
-                            .into_iter()
-                            .filter_map(|f| f.ok())
-                            .for_each(|val| {
+                            .filter_map(|f| f.ok()) {
                                 if val.key.local_name().as_ref() == b"id" {
                                     if let Ok(v) = val.decode_and_unescape_value(&reader) {
                                         if let Ok(v) = v.to_string().parse::<u16>() {
@@ -630,14 +3091,14 @@ pub(crate) mod data_types {
                                                     change_this = true;
                                                     skip = e.to_owned();
 
-                                                    writer.write_event(Event::Start(e.to_owned())).unwrap();
-                                                    element.write(&mut writer, false).unwrap();
+                                                    writer.write_event(Event::Start(e.to_owned()))?;
+                                                    element.write(&mut writer, false)?;
                                                 }
                                             };
                                         };
                                     };
                                 };
-                            });
+                            }
                         if write {
                             writer.write_event(Event::Start(e.to_owned()))?;        
                         }
@@ -661,10 +3122,9 @@ pub(crate) mod data_types {
 
             Ok((
                 modified,
-                str::from_utf8(
+                String::from_utf8_lossy(
                     &writer.into_inner().into_inner()
                 )
-                .unwrap()
                 .to_string()
             ))
         }
@@ -672,30 +3132,125 @@ pub(crate) mod data_types {
         pub fn is_synced(&self) -> bool {
             self.synced
         }
-        pub fn list(&self) {
-            let mut table: Table = Table::new();
-            table.set_titles(row!["ID", "Title", "Description", "Due"]);
-            self.elements.iter().for_each(|e| {
-                table.add_row(e.to_row());
-            });
+        /// Prints the elements as a tree of subtasks, completed ones only if
+        /// `all` is set
+        pub fn list(&self, all: bool) {
+            let visible: Vec<&AppElement> = self.elements.iter().filter(|e| all || !e.is_completed()).collect();
+            let mut table: Table = elements_table([].iter());
+            // Elements in a cycle of parents, which only the server could
+            // create, are shown at the top level as well
+            let mut stack: Vec<(&AppElement, usize)> = visible
+                .iter()
+                .filter(|e| {
+                    !visible.iter().any(|p| p.handle == e.parent_handle)
+                        || self.is_descendant(e.parent_handle, e.handle)
+                })
+                .rev()
+                .map(|e| (*e, 0))
+                .collect();
+            let mut listed: Vec<u32> = Vec::new();
+            while let Some((element, depth)) = stack.pop() {
+                if listed.contains(&element.handle) {
+                    continue;
+                }
+                listed.push(element.handle);
+                let mut title: String = element.title();
+                if depth != 0 {
+                    title = format!("{}└ {}", "  ".repeat(depth - 1), title);
+                }
+                if let Some((done, total)) = self.subtask_progress(element.handle) {
+                    title = format!("{} [{}/{}]", title, done, total);
+                }
+                table.add_row(element.row_with_title(title));
+                visible
+                    .iter()
+                    .rev()
+                    .filter(|e| e.parent_handle == element.handle)
+                    .for_each(|e| stack.push((e, depth + 1)));
+            }
             table.printstd();
+            let hidden: usize = self.elements.iter().filter(|e| !all && e.is_completed()).count();
+            if hidden != 0 {
+                println!("{} completed element(s) hidden", hidden);
+            }
         }
 
-        /// Syncs changes, fetches new elements, deletes removed elements and pushes
-        pub async fn sync(&mut self) -> Result<(), reqwest::Error> {
-            println!("Fetching new Entries...");
-            let result = self.fetch().await?;
+        /// Syncs changes, fetches new elements, deletes removed elements and pushes.
+        /// Local modifications are merged with changes made on the server since
+        /// the last sync, conflicts are resolved through the given handler.
+        /// If the registry is changed on the server while uploading, the sync
+        /// starts over and merges the new changes of the server.
+        /// If anything fails, the local state is left as it was before
+        pub async fn sync<H: SyncHandler>(&mut self, handler: &mut H) -> AppResult<SyncReport> {
+            let backup: Vec<AppElement> = self.elements.clone();
+            let synced: bool = self.synced;
+            let mut attempt: usize = 1;
+            loop {
+                match self.try_sync(handler).await {
+                    Err(AppError::RegistryChanged) if attempt < SYNC_ATTEMPTS => {
+                        self.progress("The registry was changed on the server in the meantime, merging again...");
+                        self.elements = backup.clone();
+                        attempt += 1;
+                    },
+                    Err(e) => {
+                        self.elements = backup;
+                        self.synced = synced;
+                        return Err(e);
+                    },
+                    Ok(report) => return Ok(report),
+                }
+            }
+        }
+
+        /// Fetches the registry and takes over the changes of the server for all
+        /// elements without local changes. Nothing is uploaded and no conflict
+        /// is resolved, the local changes are left for the next sync
+        pub async fn refresh(&mut self) -> AppResult<SyncReport> {
+            let mut report: SyncReport = SyncReport::default();
+            let remote: Registry = from_str(&self.fetch().await?)?;
+            self.revive_deleted_edits(&remote.entries, &mut report);
+            self.reconcile(remote.entries, &mut report);
+            self.sort_by_due();
+            Ok(report)
+        }
+
+        async fn try_sync<H: SyncHandler>(&mut self, handler: &mut H) -> AppResult<SyncReport> {
+            let mut report: SyncReport = SyncReport::default();
+
+            self.progress("Fetching new Entries...");
+            let (result, etag) = self.fetch_with_etag().await?;
+
+            self.progress("Evaluating State...");
+
+            let remote: Registry = from_str(&result)?;
+            let revision: Revision = match etag {
+                Some(etag) => Revision::ETag(etag),
+                None => Revision::Hash(registry_hash(&remote.entries)),
+            };
+            self.adopt_created_entries(&remote.entries);
+            let merged: usize = self.merge_remote(&remote.entries, handler);
+            if merged != 0 {
+                self.progress(format!("Merged {} changed field(s) from the server", merged));
+            }
+            self.revive_deleted_edits(&remote.entries, &mut report);
+
+            // Rolled over after the merge, so an occurrence another client created
+            // already is not created again, and uploaded along with the cleared rule
+            let recurring: usize = self.roll_over_recurring();
+            if recurring != 0 {
+                self.progress(format!("Created the next occurrence of {} recurring element(s)", recurring));
+            }
 
-            println!("Evaluating State...");
+            let plan: SyncPlan = self.plan(&remote.entries);
+            if !handler.review(&plan) {
+                return Err(AppError::Aborted);
+            }
 
             let (entries_deleted, answer) = self
                 .delete_removed(result.to_string())
                 .unwrap_or((false, result));
 
-            let (entries_modified, mut answer) = self.edit_entries(answer).unwrap();
-            //println!("{}", answer);
-
-            let fetched_registry: Registry = from_str(&answer).unwrap();
+            let fetched_registry: Registry = from_str(&answer)?;
 
             let mut existing_ids: Vec<u16> = fetched_registry.entries
                 .clone()
@@ -704,32 +3259,108 @@ pub(crate) mod data_types {
                 .filter_map(|e| e.id)
                 .collect();
 
-            let (entries_added, new_ids) = self.add_missing_ids(&mut existing_ids);
+            // IDs are assigned before the edits are written, so subtasks of
+            // new elements are uploaded with the ID of their parent
+            let (entries_added, new_ids) = self.add_missing_ids(&mut existing_ids)?;
+            self.link_parents();
+            // New subtasks are created with the ID of their parent, no edit is left
+            self.elements
+                .iter_mut()
+                .filter(|e| e.id.is_some_and(|id| new_ids.contains(&id)))
+                .for_each(|e| e.modified = false);
+
+            let (entries_modified, mut answer) = self.edit_entries(answer)?;
+            //println!("{}", answer);
 
             if entries_added {
-                answer = self.insert_created_entries(answer, new_ids);
+                answer = self.insert_created_entries(answer, new_ids)?;
             }
 
             let needs_upload: bool = entries_deleted || entries_modified || entries_added;
 
             if needs_upload {
-                println!("Uploading Changes...");
-                self.upload(answer.clone()).await?;
+                self.progress("Uploading Changes...");
+                let uploaded: Vec<AppElement> = from_str::<Registry>(&answer)?.entries;
+                let operations: Vec<(Operation, AppElement)> = pending_operations(&remote.entries, &uploaded);
+                let changes: ChangeSet = ChangeSet::new(&operations, &answer)?;
+                self.journal_record(operations)?;
+                self.upload_changes(changes, answer.clone(), &revision).await?;
             }
 
+            let synced_registry: Registry = from_str(&answer)?;
+            self.registry_hash = Some(registry_hash(&synced_registry.entries));
+            self.base = synced_registry.entries;
 
-            self.add_new_elements(fetched_registry.entries);
+            self.reconcile(self.base.clone(), &mut report);
 
             self.sort_by_due();
 
+            // The server has everything of the local state now
+            if let Some(Err(e)) = self.journal.as_mut().map(|e| e.clear()) {
+                self.progress(e);
+            }
+
             self.synced = true;
-            println!("Done!");
-            Ok(())
+            self.last_sync = Some(Utc::now().timestamp());
+            self.freshness = Freshness::Fresh;
+            self.progress(&report);
+            self.progress("Done!");
+            Ok(report)
+        }
+
+        /// Marks the element as completed or open again. Completing a recurring
+        /// element creates its next occurrence, which takes over the rule.
+        /// Returns whether the state of the element changed
+        pub fn set_completed(&mut self, handle: u32, completed: bool) -> bool {
+            let now: u32 = Utc::now().timestamp().try_into().unwrap_or(u32::MAX);
+            let Some(element) = self.get_element(handle) else {return false};
+            if !element.set_completed(completed) {
+                return false;
+            }
+            if completed {
+                // The rule moves on to the next occurrence, if there is one
+                let next: Option<AppElement> = element.next_occurrence(now);
+                element.recurrence = None;
+                if let Some(next) = next {
+                    self.insert(next);
+                }
+            }
+            self.synced = false;
+            true
+        }
+
+        /// Creates the next occurrence of open recurring elements whose due date
+        /// or event passed, the passed element keeps no rule. Returns the number of
+        /// occurrences created
+        pub fn roll_over_recurring(&mut self) -> usize {
+            let now: u32 = Utc::now().timestamp().try_into().unwrap_or(u32::MAX);
+            let mut created: Vec<AppElement> = Vec::new();
+            self.elements
+                .iter_mut()
+                .filter(|e| !e.removed && !e.is_completed() && e.over_at().is_some_and(|over| over < now))
+                .for_each(|e| {
+                    if let Some(next) = e.next_occurrence(now) {
+                        e.set_recurrence(None);
+                        created.push(next);
+                    }
+                });
+            let count: usize = created.len();
+            created.into_iter().for_each(|e| self.insert(e));
+            if count != 0 {
+                self.synced = false;
+            }
+            count
         }
 
-        pub fn remove(&mut self, id: u16) -> bool {
-            let Some(posi) = self.elements.iter().position(|e| e.id == Some(id)) else {return false};
-            self.elements[posi].removed = true;
+        /// Marks the element to be deleted on the server with the next sync.
+        /// Elements that never made it to the server are dropped right away
+        pub fn remove(&mut self, handle: u32) -> bool {
+            let Some(posi) = self.elements.iter().position(|e| e.handle == handle) else {return false};
+            if self.elements[posi].id.is_none() {
+                self.elements.remove(posi);
+            } else {
+                self.elements[posi].removed = true;
+            }
             true
         }
     }
@@ -746,7 +3377,9 @@ pub(crate) mod data_types {
         Help,       // H
                     // I, J, K
         List,       // L
-                    // M, N, O, P
+                    // M, N
+        Complete,   // O
+        Reopen,     // P
         Quit,       // Q
         Remove,     // R
         Sync,       // S
@@ -754,9 +3387,9 @@ pub(crate) mod data_types {
         None,
     }
 
-    impl ToString for AppCommand {
-        fn to_string(&self) -> String {
-            match self {
+    impl fmt::Display for AppCommand {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let displ: &str = match self {
                 Self::Add       => "[a]dd",
                 Self::Boiling   => "[b]oiling",
                 Self::Config    => "[c]onfig",
@@ -765,11 +3398,14 @@ pub(crate) mod data_types {
                 Self::Filter    => "[f]ilter",
                 Self::Help      => "[h]elp",
                 Self::List      => "[l]ist",
+                Self::Complete  => "c[o]mplete",
+                Self::Reopen    => "reo[p]en",
                 Self::Quit      => "[q]uit",
                 Self::Remove    => "[r]emove",
                 Self::Sync      => "[s]ync",
                 Self::None      => "[n]one",
-            }.to_string()
+            };
+            write!(f, "{}", displ)
         }
     }
 
@@ -782,11 +3418,13 @@ pub(crate) mod data_types {
                 3 => Self::Edit,
                 4 => Self::Add,
                 5 => Self::Remove,
-                6 => Self::Direct,
-                7 => Self::Boiling,
-                8 => Self::Config,
-                9 => Self::Help,
-                10 => Self::Quit,
+                6 => Self::Complete,
+                7 => Self::Reopen,
+                8 => Self::Direct,
+                9 => Self::Boiling,
+                10 => Self::Config,
+                11 => Self::Help,
+                12 => Self::Quit,
                 _ => Self::None,
             }
         }
@@ -804,7 +3442,7 @@ pub(crate) mod data_types {
                 result.push(e);
                 i += 1;
             }
-            return result;
+            result
             /*
             vec![
                 Self::Sync,
@@ -822,7 +3460,7 @@ pub(crate) mod data_types {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq)]
+    #[derive(Serialize, Deserialize, PartialEq, Clone)]
     pub enum AuthMethod {
         Token,
         Password
@@ -848,12 +3486,57 @@ pub(crate) mod data_types {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq)]
+    /// Timeouts and retries of the requests to the server
+    #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+    #[serde(default)]
+    pub struct NetworkSettings {
+        /// Seconds until a request is given up
+        pub request_timeout: u64,
+        /// Seconds until connecting to the server is given up
+        pub connect_timeout: u64,
+        /// How often a failed request is sent again
+        pub retries: u32,
+        /// Milliseconds to wait before the first retry, doubled for every further retry
+        pub backoff: u64,
+        /// The longest time in milliseconds to wait between retries
+        pub max_backoff: u64,
+    }
+
+    impl ::std::default::Default for NetworkSettings {
+        fn default() -> Self {
+            Self {
+                request_timeout: 30,
+                connect_timeout: 10,
+                retries: 3,
+                backoff: 500,
+                max_backoff: 8000,
+            }
+        }
+    }
+
+    impl fmt::Display for NetworkSettings {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "Timeouts: {}s per request, {}s to connect\nRetries: {} (waiting {}ms up to {}ms)",
+                self.request_timeout, self.connect_timeout, self.retries, self.backoff, self.max_backoff
+            )
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Clone)]
     pub struct AppConfig {
         pub server_address: String,
         pub username: String,
         pub secret: String,
         pub auth_method: AuthMethod,
+        /// Seconds between syncs in the background of the interactive session,
+        /// no background syncs if not set. Their outcome shows with the next
+        /// prompt, an open prompt is not updated
+        #[serde(default)]
+        pub auto_sync: Option<u64>,
+        #[serde(default)]
+        pub network: NetworkSettings,
     }
 
     /// Construct a default AppConfig
@@ -864,21 +3547,38 @@ pub(crate) mod data_types {
                 username: "<YOUR USERNAME>".to_string(),
                 secret: "<YOUR TOKEN / SECRET>".to_string(),
                 auth_method: AuthMethod::Token,
+                auto_sync: None,
+                network: NetworkSettings::default(),
             }
         }
     }
 
     impl fmt::Display for AppConfig {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let auto_sync: String = self.auto_sync.map(|e| format!("every {}s", e)).unwrap_or("off".to_string());
+            if self.is_local() {
+                return write!(f, "Registry: {}\nAuto Sync: {}", self.server_address, auto_sync);
+            }
             write!(
                 f,
-                "Server: {}\nUsername: {}\nSecret: {}\nAuth Method: {}",
-                self.server_address, self.username, "*".repeat(self.secret.len()), self.auth_method
+                "Server: {}\nUsername: {}\nSecret: {}\nAuth Method: {}\nAuto Sync: {}\n{}",
+                self.server_address,
+                self.username,
+                "*".repeat(self.secret.len()),
+                self.auth_method,
+                auto_sync,
+                self.network
             )
         }
     }
 
     impl AppConfig {
+        /// Returns whether the registry is a local file instead of a server,
+        /// so there are no credentials needed
+        pub(crate) fn is_local(&self) -> bool {
+            self.server_address.starts_with("file://") || self.server_address.starts_with("memory://")
+        }
+
         /// Returns if the element is the same as the default options
         pub(crate) fn is_default(&self) -> bool {
             self == &Self::default()
@@ -896,6 +3596,8 @@ pub(crate) mod data_types {
                 username: "".to_string(),
                 secret: "".to_string(),
                 auth_method: AuthMethod::Token,
+                auto_sync: None,
+                network: NetworkSettings::default(),
             }
         }
 
@@ -905,6 +3607,8 @@ pub(crate) mod data_types {
                 username,
                 secret,
                 auth_method,
+                auto_sync: None,
+                network: NetworkSettings::default(),
             }
         }
     }
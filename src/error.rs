@@ -0,0 +1,101 @@
+use std::fmt;
+
+/// Everything that can go wrong while talking to the server or handling the registry
+#[derive(Debug)]
+pub enum AppError {
+    /// The server could not be reached or the connection broke
+    Network(reqwest::Error),
+    /// The server answered with an unexpected HTTP status
+    Status(u16),
+    /// The server did not accept the configured credentials
+    Auth(u16),
+    /// The server answered with something else than XML
+    ContentType(String),
+    /// The registry XML could not be processed
+    Xml(quick_xml::Error),
+    /// The registry XML could not be read into elements
+    Parse(quick_xml::DeError),
+    /// The configuration can not be used
+    Config(String),
+    /// There is no element with the given ID or label
+    UnknownElement(String),
+    /// The sync was aborted before anything was sent
+    Aborted,
+    /// The registry on the server was changed by someone else during the sync
+    RegistryChanged,
+    /// The whole registry would be uploaded without a revision the server checks
+    UnprotectedUpload,
+    /// The journal of pending operations could not be written
+    Journal(std::io::Error),
+    /// The registry file could not be read or written
+    Storage(std::io::Error),
+    /// The registry file stayed locked by another process
+    Locked(String),
+    /// Every ID of the registry is taken by an entry
+    NoFreeId,
+    /// The element can not become the parent, as it is unknown or a subtask
+    InvalidParent(String),
+    /// The element has subtasks that would be affected as well
+    HasSubtasks(String),
+    /// The times given for an event do not fit together
+    InvalidEvent(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// Returns the error matching a HTTP status, if the status is not a success
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            200..=299 => None,
+            401 | 403 => Some(Self::Auth(status)),
+            412 => Some(Self::RegistryChanged),
+            _ => Some(Self::Status(status)),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "Could not communicate with the server ({})", e),
+            Self::Status(status) => write!(f, "The server answered with HTTP status {}", status),
+            Self::Auth(status) => write!(f, "The server rejected the credentials (HTTP status {})", status),
+            Self::ContentType(content_type) => write!(f, "The server answered with '{}' instead of XML", content_type),
+            Self::Xml(e) => write!(f, "Could not process the registry ({})", e),
+            Self::Parse(e) => write!(f, "Could not read the registry ({})", e),
+            Self::Config(msg) => write!(f, "The config can not be used: {}", msg),
+            Self::UnknownElement(id) => write!(f, "There is no element with ID {}", id),
+            Self::Aborted => write!(f, "The sync was aborted, nothing was sent to the server"),
+            Self::RegistryChanged => write!(f, "The registry was changed on the server during the sync, please try again"),
+            Self::UnprotectedUpload => write!(f, "The server supports neither ETags nor change sets, uploading the whole registry could overwrite the changes of other clients. Please update the server"),
+            Self::Journal(e) => write!(f, "Could not write the journal of pending operations ({})", e),
+            Self::Storage(e) => write!(f, "Could not access the registry file ({})", e),
+            Self::Locked(path) => write!(f, "The registry file {} is locked by another process", path),
+            Self::NoFreeId => write!(f, "All {} IDs of the registry are taken", u16::MAX),
+            Self::InvalidParent(label) => write!(f, "The element {} can not be the parent", label),
+            Self::HasSubtasks(label) => write!(f, "The element {} has subtasks", label),
+            Self::InvalidEvent(msg) => write!(f, "The times of the event can not be used: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl From<quick_xml::Error> for AppError {
+    fn from(e: quick_xml::Error) -> Self {
+        Self::Xml(e)
+    }
+}
+
+impl From<quick_xml::DeError> for AppError {
+    fn from(e: quick_xml::DeError) -> Self {
+        Self::Parse(e)
+    }
+}
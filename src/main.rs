@@ -1,23 +1,27 @@
 mod data;
-use crate::data::data_types::{AppState, AppConfig, AppCommand, AppElement, AuthMethod};
+mod error;
+#[cfg(test)]
+mod tests;
+use crate::error::{AppError, AppResult};
+use crate::data::data_types::{AppState, AppConfig, AppCache, AppCommand, AppElement, AuthMethod, Conflict, Freshness, Journal, MergeField, NetworkSettings, Priority, Recurrence, Resolution, SyncHandler, SyncPlan, EventTimes, elements_table, event_end};
 
 #[macro_use] extern crate prettytable;
-use confy;
 use std::env;
 use std::fs;
 use std::io;
-use std::ops::{Add, Sub};
-use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use chrono::{TimeZone, Utc, LocalResult};
 use clap::{Arg, Command, ArgMatches, crate_authors, crate_description, crate_version, ArgAction};
 use dialoguer::{Input, Confirm, Password, FuzzySelect, Select, theme::ColorfulTheme, console::Term};
 use prettytable::Table;
+use tokio::sync::Mutex;
 
 
 
 /// Read the app configuration
 fn obtain_app_config() -> Option<AppConfig> {
-    let mut path = dirs::config_dir().unwrap_or(PathBuf::new());
+    let mut path = dirs::config_dir().unwrap_or_default();
     path.push("freemind/");
     fs::create_dir_all(path.clone()).ok();
     path.push("freemind-cli.config");
@@ -26,22 +30,104 @@ fn obtain_app_config() -> Option<AppConfig> {
 
 /// Save the app configuration
 fn write_app_config(config: &AppConfig) -> Option<()> {
-    let mut path = dirs::config_dir().unwrap_or(PathBuf::new());
+    let mut path = dirs::config_dir().unwrap_or_default();
     path.push("freemind/");
     fs::create_dir_all(path.clone()).ok();
     path.push("freemind-cli.config");
-    confy::store_path(path, config).ok();
-    Some(())
+    confy::store_path(path, config).ok()
+}
+
+/// Read the cached local state
+fn obtain_app_cache() -> Option<AppCache> {
+    let mut path = dirs::config_dir().unwrap_or_default();
+    path.push("freemind/");
+    fs::create_dir_all(path.clone()).ok();
+    path.push("freemind-cli.cache");
+    confy::load_path(path).ok()
+}
+
+/// Save the local state to the cache
+fn write_app_cache(cache: &AppCache) -> Option<()> {
+    let mut path = dirs::config_dir().unwrap_or_default();
+    path.push("freemind/");
+    fs::create_dir_all(path.clone()).ok();
+    path.push("freemind-cli.cache");
+    confy::store_path(path, cache).ok()
+}
+
+/// Attaches the journal of pending operations to the given state, replaying
+/// the operations of an interrupted run
+fn attach_journal(state: &mut AppState) {
+    let mut path = dirs::config_dir().unwrap_or_default();
+    path.push("freemind/");
+    fs::create_dir_all(path.clone()).ok();
+    path.push("freemind-cli.journal");
+    let replayed: usize = state.attach_journal(Journal::open(path));
+    if replayed != 0 {
+        println!("Replayed {} unconfirmed operation(s) of an interrupted run, sync to send them", replayed);
+    }
+}
+
+/// Creates the next occurrence of recurring elements that passed since the
+/// last sync and tells the user about it
+fn roll_over_recurring(state: &mut AppState) {
+    let recurring: usize = state.roll_over_recurring();
+    if recurring != 0 {
+        println!("Created the next occurrence of {} recurring element(s)", recurring);
+    }
+}
+
+/// Loads the cache into the given state and tells the user about it
+async fn load_cached_state(state: &mut AppState) {
+    let cache_used: bool = obtain_app_cache()
+        .map(|e| state.load_cache(e))
+        .unwrap_or(false);
+    attach_journal(state);
+    roll_over_recurring(state);
+    if !cache_used || state.get_elements().is_empty() {
+        return;
+    }
+    let last_sync: String = state
+        .last_sync()
+        .and_then(|e| Utc.timestamp_opt(e, 0).single())
+        .map(|e| e.with_timezone(&chrono::Local).to_rfc2822())
+        .unwrap_or("never".to_string());
+    println!("Loaded {} cached elements (last synced: {})", state.get_elements().len(), last_sync);
+    match state.check_freshness().await {
+        Ok(Freshness::Fresh) => println!("The cache is up to date with the server"),
+        Ok(_) => println!("The cache is stale, sync to get the latest changes from the server"),
+        Err(AppError::Auth(_)) => println!("The server rejected the credentials, use the config command to update them"),
+        Err(_) => println!("The server could not be reached, working offline"),
+    }
+    if !state.is_synced() {
+        println!("There are unsynced local changes");
+    }
 }
 
 /// Configuration Setup Dialog
 fn setup_config(prev_config: &AppConfig) -> Result<AppConfig, std::io::Error> {
     println!("\n   ### Config Setup: ###\n");
     let server_address: String = Input::new()
-        .with_prompt("URL of the server to connect to")
+        .with_prompt("URL of the server to connect to, or file:// and the path of a local registry file")
         .with_initial_text(&prev_config.server_address)
         .interact_text()?;
 
+    let mut config: AppConfig = AppConfig::new(
+        server_address,
+        String::new(),
+        String::new(),
+        AuthMethod::Token,
+    );
+    config.auto_sync = auto_sync_dialog(prev_config.auto_sync)?;
+    config.network = prev_config.network.clone();
+    if config.is_local() {
+        // A local registry file needs no credentials
+        return confirm_config(config);
+    }
+    if Confirm::new().with_prompt("Do you want to change the network settings?").default(false).interact()? {
+        config.network = network_dialog(&prev_config.network)?;
+    }
+
     let username: String = Input::new()
         .with_prompt("Your username")
         .with_initial_text(&prev_config.username)
@@ -49,120 +135,407 @@ fn setup_config(prev_config: &AppConfig) -> Result<AppConfig, std::io::Error> {
 
     let auth_method: AuthMethod = AuthMethod::from(Select::with_theme(&ColorfulTheme::default())
         .with_prompt("How do you want to authenticate?")
-        .items(&vec!["API Token", "Password"])
+        .items(&["API Token", "Password"])
         .default(0)
         .interact_on_opt(&Term::stderr())?.unwrap_or(0));
 
+    let keep_secret: bool = auth_method == prev_config.auth_method && !prev_config.secret.is_empty();
     let secret: String = match auth_method {
         AuthMethod::Token => Input::new()
             .with_prompt("Your API Token")
+            .with_initial_text(if keep_secret {prev_config.secret.as_str()} else {""})
             .interact_text()?,
-        AuthMethod::Password => Password::new()
-            .with_prompt("Your Password")
-            .interact()?
+        AuthMethod::Password => {
+            let password: String = Password::new()
+                .with_prompt(if keep_secret {"Your Password (leave empty to keep the current one)"} else {"Your Password"})
+                .allow_empty_password(keep_secret)
+                .interact()?;
+            if password.is_empty() {prev_config.secret.clone()} else {password}
+        }
     };
 
-    let config: AppConfig = AppConfig::new(
-        server_address,
-        username,
-        secret,
-        auth_method,
-    );
+    config.username = username;
+    config.secret = secret;
+    config.auth_method = auth_method;
+    confirm_config(config)
+}
+
+/// Asks for the seconds between background syncs, returns None to turn them off
+fn auto_sync_dialog(prev: Option<u64>) -> Result<Option<u64>, std::io::Error> {
+    let seconds: u64 = Input::new()
+        .with_prompt("Seconds between syncs in the background, 0 to turn them off")
+        .with_initial_text(prev.unwrap_or(0).to_string())
+        .interact_text()?;
+    Ok(Some(seconds).filter(|e| *e != 0))
+}
 
+/// Network Settings Dialog, starting from the given settings
+fn network_dialog(prev: &NetworkSettings) -> Result<NetworkSettings, std::io::Error> {
+    Ok(NetworkSettings {
+        request_timeout: Input::new()
+            .with_prompt("Seconds until a request is given up")
+            .with_initial_text(prev.request_timeout.to_string())
+            .interact_text()?,
+        connect_timeout: Input::new()
+            .with_prompt("Seconds until connecting to the server is given up")
+            .with_initial_text(prev.connect_timeout.to_string())
+            .interact_text()?,
+        retries: Input::new()
+            .with_prompt("How often a failed request is sent again")
+            .with_initial_text(prev.retries.to_string())
+            .interact_text()?,
+        backoff: Input::new()
+            .with_prompt("Milliseconds to wait before the first retry")
+            .with_initial_text(prev.backoff.to_string())
+            .interact_text()?,
+        max_backoff: Input::new()
+            .with_prompt("Longest time in milliseconds to wait between retries")
+            .with_initial_text(prev.max_backoff.to_string())
+            .interact_text()?,
+    })
+}
+
+/// Shows the entered config and lets the user accept it or enter it again
+fn confirm_config(config: AppConfig) -> Result<AppConfig, std::io::Error> {
     println!("\nDone! You entered the following config:\n\n{}\n", config);
     if Confirm::new().with_prompt("Do you want to accept this config?").interact()? {
-        return Ok(config);
+        Ok(config)
     } else {
         println!("\n");
-        return setup_config(&config);
+        setup_config(&config)
     }
 
 }
 
+/// Resolves sync conflicts by asking the user which version to keep
+struct DialogSyncHandler;
+
+impl SyncHandler for DialogSyncHandler {
+    fn resolve(&mut self, conflict: &Conflict) -> Resolution {
+        println!("\nConflict in the {} of element {}:", conflict.field, conflict.id);
+        println!("  Last synced: {}", conflict.base);
+        println!("  Local:       {}", conflict.local);
+        println!("  Server:      {}", conflict.remote);
+        let selection: usize = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Which version do you want to keep?")
+            .items(&["local", "server"])
+            .default(0)
+            .interact_on_opt(&Term::stderr())
+            .unwrap_or(None)
+            .unwrap_or(0);
+        match selection {
+            1 => Resolution::Remote,
+            _ => Resolution::Local,
+        }
+    }
+
+    fn review(&mut self, plan: &SyncPlan) -> bool {
+        if plan.is_empty() {
+            return true;
+        }
+        println!("\nThe following changes are about to be sent to the server:\n\n{}\n", plan);
+        Confirm::new()
+            .with_prompt("Do you want to send these changes?")
+            .default(true)
+            .interact()
+            .unwrap_or(false)
+    }
+}
+
+/// Syncs in the background without asking anything. Runs into conflicts are
+/// aborted, so they are resolved by the user in a manual sync
+#[derive(Default)]
+struct BackgroundSyncHandler {
+    conflicts: usize,
+}
+
+impl SyncHandler for BackgroundSyncHandler {
+    fn resolve(&mut self, _conflict: &Conflict) -> Resolution {
+        self.conflicts += 1;
+        Resolution::Local
+    }
+
+    fn review(&mut self, _plan: &SyncPlan) -> bool {
+        self.conflicts == 0
+    }
+}
+
+/// How often the background sync checks whether it got enabled, in seconds
+const AUTO_SYNC_CHECK: u64 = 10;
+
+/// Syncs the shared state in the interval set in its config, until the task
+/// is aborted. A sync is skipped while a command works on the state. Nothing
+/// is printed, the outcome is left as a notice for the next prompt instead
+async fn auto_sync(state: Arc<Mutex<AppState>>, notices: Arc<Mutex<Vec<String>>>) {
+    loop {
+        let interval: Option<u64> = state.lock().await.config().auto_sync.filter(|e| *e != 0);
+        let Some(interval) = interval else {
+            tokio::time::sleep(Duration::from_secs(AUTO_SYNC_CHECK)).await;
+            continue;
+        };
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        let Ok(mut state) = state.try_lock() else {continue};
+        let mut handler: BackgroundSyncHandler = BackgroundSyncHandler::default();
+        state.set_quiet(true);
+        let result = state.sync(&mut handler).await;
+        state.set_quiet(false);
+        let notice: Option<String> = match result {
+            Ok(report) if report.is_empty() => None,
+            Ok(report) => Some(format!("Synced in the background:\n{}", report)),
+            Err(AppError::Aborted) => Some(format!(
+                "The background sync ran into {} conflict(s), sync manually to resolve them",
+                handler.conflicts
+            )),
+            Err(e) => Some(format!("The background sync failed! {}", e)),
+        };
+        write_app_cache(&state.to_cache());
+        drop(state);
+        if let Some(notice) = notice {
+            notices.lock().await.push(notice);
+        }
+    }
+}
+
+/// Resolves sync conflicts without asking, always keeping the same side.
+/// Only shows the changes without sending them on a dry run
+struct FixedSyncHandler {
+    resolution: Resolution,
+    dry_run: bool,
+}
+
+impl SyncHandler for FixedSyncHandler {
+    fn resolve(&mut self, conflict: &Conflict) -> Resolution {
+        eprintln!(
+            "Conflict in the {} of element {}: keeping the {} version",
+            conflict.field,
+            conflict.id,
+            if self.resolution == Resolution::Local {"local"} else {"server"}
+        );
+        self.resolution
+    }
+
+    fn review(&mut self, plan: &SyncPlan) -> bool {
+        if self.dry_run {
+            println!("{}", plan);
+        }
+        !self.dry_run
+    }
+}
+
 /// Helper with chrono that creates a timestamp that is *days* in the future
 fn chrono_date_helper(days: i64) -> Option<u32> {
     let now = chrono::offset::Local::now();
     u32::try_from(if days != 0 {
-        let tmrw = if let Ok(ut) = days.try_into() {
-            now.add(chrono::naive::Days::new(ut))
+        let tmrw = if days > 0 {
+            now.checked_add_days(chrono::naive::Days::new(days.unsigned_abs()))?
         } else {
-            now.sub(chrono::naive::Days::new((-1*days).try_into().unwrap_or(0)))
+            now.checked_sub_days(chrono::naive::Days::new(days.unsigned_abs()))?
         };
-        chrono::DateTime::from_local(
-            chrono::naive::NaiveDateTime::new(
-                tmrw.date_naive(),
-                chrono::naive::NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
-            now.offset().to_owned())
+        chrono::naive::NaiveDateTime::new(
+            tmrw.date_naive(),
+            chrono::naive::NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .unwrap_or(now)
     } else {
         now
     }.naive_utc().and_utc().timestamp()).ok()
 }
 
+/// Parses a datetime as entered by the user, either a number of days relative
+/// to today (e.g. '+1', '-1') or a full date with time (e.g. '04.06.23 19:00')
+/// and returns the unix timestamp
+fn parse_datetime_input(input: &str) -> Option<u32> {
+    if let Some(days) = input.strip_prefix('+') {
+        chrono_date_helper(days.parse::<i64>().ok()?)
+    } else if input.starts_with('-') {
+        chrono_date_helper(input.parse::<i64>().ok()?)
+    } else {
+        let offset: String = chrono::Local::now().format("%z").to_string();
+        u32::try_from(
+            chrono::DateTime::parse_from_str(
+                &format!("{} {}", input, offset), "%d.%m.%y %H:%M %z"
+            )
+            .ok()?
+            .timestamp()
+        ).ok()
+    }
+}
+
 /// Questions the user to input a datetime and returns the unix timestamp
 fn get_datetime_from_user() -> Result<Option<u32>, std::io::Error> {
     let entered_input: String = Input::new()
                 .with_prompt("Enter a number of days (e.g. '+1', '-1') or a full date with time (e.g. '04.06.23 19:00')")
                 .validate_with(|input: &String| {
-                    if input.starts_with("+") {
-                        input[1..].parse::<i64>().is_ok()
-                    } else if input.starts_with("-") {
-                        input[0..].parse::<i64>().is_ok()
-                    } else {
-                        chrono::naive::NaiveDateTime::parse_from_str(input, "%d.%m.%y %H:%M").is_ok()
-                    }.then_some(()).ok_or("Invalid format")
+                    parse_datetime_input(input).is_some().then_some(()).ok_or("Invalid format")
                 })
                 .interact_text()?;
 
-            if entered_input.starts_with("+") {
-                Ok(chrono_date_helper(entered_input[1..].parse::<i64>().unwrap_or(0)))
-            } else if entered_input.starts_with("-") {
-                Ok(chrono_date_helper(entered_input[0..].parse::<i64>().unwrap_or(0)))
-            } else {
-                let offset: String = chrono::Local::now().format("%z").to_string();
-                Ok(u32::try_from(
-                    chrono::DateTime::parse_from_str(
-                        &format!("{} {}", entered_input, offset),"%d.%m.%y %H:%M %z"
-                    )
-                    .unwrap()
-                    .naive_utc()
-                    .timestamp()
-                ).ok())
-            }
+            Ok(parse_datetime_input(&entered_input))
 }
 
-fn get_element_id_from_user(state: &AppState) -> Result<Option<u16>, std::io::Error> {
-    let mut ids: Vec<String> = state
-        .get_ids(true)
-        .into_iter()
+/// Parses a duration like '90m', '1h30m' or '2d' and returns the number of seconds
+fn parse_duration_input(input: &str) -> Option<u32> {
+    let mut seconds: u32 = 0;
+    let mut number: String = String::new();
+    for c in input.chars().filter(|e| !e.is_whitespace()) {
+        let unit: u32 = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            },
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            _ => return None,
+        };
+        seconds = seconds.checked_add(number.parse::<u32>().ok()?.checked_mul(unit)?)?;
+        number.clear();
+    }
+    (number.is_empty() && !input.trim().is_empty()).then_some(seconds)
+}
+
+/// Lets the user choose whether the element is an event and when it takes
+/// place, offering to keep the times of the current element. Returns the
+/// start, the end and whether the event takes whole days
+fn get_event_from_user(current: Option<&AppElement>) -> Result<EventTimes, std::io::Error> {
+    let current: Option<&AppElement> = current.filter(|e| e.is_event());
+    let mut items: Vec<String> = ["no event", "all day", "with times"]
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    if let Some(current) = current {
+        items.insert(0, format!("keep ({})", current.field_string(MergeField::Event)));
+    }
+    let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Event")
+        .items(&items)
+        .default(0)
+        .interact_on_opt(&Term::stderr())?.unwrap_or(0);
+
+    if let Some(current) = current.filter(|_| selection == 0) {
+        return Ok((current.start(), current.end(), current.is_all_day()));
+    }
+    let all_day: bool = match items[selection].as_str() {
+        "all day" => true,
+        "with times" => false,
+        _ => return Ok((None, None, false)),
+    };
+    println!("Set the start");
+    let start: Option<u32> = get_datetime_from_user()?;
+    let duration: String = Input::new()
+        .with_prompt("Duration (e.g. '90m', '1h30m', '2d' or leave empty)")
+        .allow_empty(true)
+        .validate_with(|input: &String| {
+            (input.is_empty() || parse_duration_input(input).is_some()).then_some(()).ok_or("Invalid format")
+        })
+        .interact_text()?;
+    let end: Option<u32> = start.zip(parse_duration_input(&duration)).map(|(start, e)| event_end(start, e, all_day));
+    Ok((start, end, all_day))
+}
+
+/// Lets the user select a priority, starting at the given one
+fn get_priority_from_user(initial: Priority) -> Result<Priority, std::io::Error> {
+    let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Priority")
+        .items(&Priority::ALL)
+        .default(Priority::ALL.iter().position(|e| *e == initial).unwrap_or(0))
+        .interact_on_opt(&Term::stderr())?.unwrap_or(0);
+    Ok(Priority::ALL.get(selection).copied().unwrap_or(initial))
+}
+
+/// Lets the user choose how an element repeats, offering to keep the current rule
+fn get_recurrence_from_user(current: Option<&Recurrence>) -> Result<Option<Recurrence>, std::io::Error> {
+    let mut items: Vec<String> = ["never", "daily", "weekly", "monthly", "yearly", "custom"]
+        .iter()
         .map(|e| e.to_string())
         .collect();
-    ids.push("Exit".to_string());
-    let last_element = ids.len() - 1;
+    if let Some(current) = current {
+        items.insert(0, format!("keep ({})", current));
+    }
+    let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Repeat")
+        .items(&items)
+        .default(0)
+        .interact_on_opt(&Term::stderr())?.unwrap_or(0);
+
+    if current.is_some() && selection == 0 {
+        return Ok(current.cloned());
+    }
+    match items[selection].as_str() {
+        "never" => Ok(None),
+        "custom" => {
+            let rule: String = Input::new()
+                .with_prompt("Rule (e.g. FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;COUNT=5)")
+                .validate_with(|input: &String| input.parse::<Recurrence>().map(|_| ()))
+                .interact_text()?;
+            Ok(rule.parse().ok())
+        },
+        frequency => Ok(frequency.parse().ok()),
+    }
+}
+
+/// Lets the user choose the parent of an element, which can't be the element
+/// itself or one of its subtasks. Returns the handle of the parent, None for
+/// a top level element
+fn get_parent_from_user(state: &AppState, handle: Option<u32>) -> Result<Option<u32>, std::io::Error> {
+    let subtasks: Vec<u32> = handle.map(|e| state.subtasks(e)).unwrap_or_default();
+    let current: u32 = state
+        .get_elements()
+        .iter()
+        .find(|e| Some(e.handle()) == handle)
+        .map(|e| e.parent_handle())
+        .unwrap_or(0);
+    let candidates: Vec<(u32, String)> = state.get_labels(|e| Some(e.handle()) != handle && !subtasks.contains(&e.handle()));
+    let mut items: Vec<String> = vec!["none".to_string()];
+    items.extend(candidates.iter().map(|(handle, label)| {
+        let title: String = state.get_elements().iter().find(|e| e.handle() == *handle).map(|e| e.title()).unwrap_or_default();
+        format!("{} {}", label, title)
+    }));
+    let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Subtask of")
+        .items(&items)
+        .default(candidates.iter().position(|e| e.0 == current).map(|e| e + 1).unwrap_or(0))
+        .interact_on_opt(&Term::stderr())?.unwrap_or(0);
+
+    Ok(selection.checked_sub(1).and_then(|e| candidates.get(e)).map(|e| e.0))
+}
+
+/// Lets the user select one of the elements matching the filter by its ID or,
+/// if it is not synced yet, its label like 'new1'. Returns the handle of the
+/// selected element
+fn get_element_handle_from_user(state: &AppState, filter: impl Fn(&AppElement) -> bool) -> Result<Option<u32>, std::io::Error> {
+    let elements: Vec<(u32, String)> = state.get_labels(filter);
+    let mut labels: Vec<String> = elements.iter().map(|e| e.1.clone()).collect();
+    labels.push("Exit".to_string());
+    let last_element = labels.len() - 1;
     let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
         .with_prompt("#")
-        .items(&ids)
+        .items(&labels)
         .default(last_element)
         .interact_on_opt(&Term::stderr())?.unwrap_or(0);
 
-    if selection == last_element {
-        Ok(None)
-    } else {
-        Ok(Some(ids[selection].parse::<u16>().unwrap()))
-    }
+    Ok(elements.get(selection).map(|e| e.0))
 }
 
-fn filter_menu(state: &mut AppState) -> Result<(), std::io::Error> {
+/// Filter Dialog, prints the given elements matching the filter
+fn filter_menu(elements: &[AppElement]) -> Result<(), std::io::Error> {
     let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
         .with_prompt("Filter according to")
-        .items(&["due", "keyword"])
+        .items(&["due", "keyword", "priority", "completed"])
         .default(0)
         .interact_on_opt(&Term::stderr())?.unwrap_or(0);
 
-    let mut table = Table::new();
-    table.set_titles(row!["ID", "Title", "Description", "Due"]);
+    let mut table: Table = elements_table([].iter());
 
     match selection {
         0 => { // due
+            let kind_selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
+                .with_prompt("Filter")
+                .items(&["tasks and events", "tasks", "events"])
+                .default(0)
+                .interact_on_opt(&Term::stderr())?.unwrap_or(0);
             let due_selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
                 .with_prompt("Filter due")
                 .items(&["over", "the next day", "upcoming week", "next 4 weeks", "custom", "range"])
@@ -202,12 +575,20 @@ fn filter_menu(state: &mut AppState) -> Result<(), std::io::Error> {
                 _ => {return Ok(())},
             };
 
-            state
-                .get_elements()
+            elements
                 .iter()
-                .filter(|e| {
-                    let timestamp_element = e.due().unwrap_or(u32::MAX);
-                    timestamp_element > timestamp_start && timestamp_element < timestamp_end
+                .filter(|e| !e.is_completed())
+                .filter(|e| match e.is_event() {
+                    true if kind_selection == 1 => false,
+                    false if kind_selection == 2 => false,
+                    // Events are over once they ended, in the other ranges
+                    // they only have to take place at some time
+                    true if due_selection == 0 => e.over_at().is_some_and(|over| over < timestamp_end),
+                    true => e.overlaps(timestamp_start, timestamp_end),
+                    false => {
+                        let timestamp_element = e.due().unwrap_or(u32::MAX);
+                        timestamp_element > timestamp_start && timestamp_element < timestamp_end
+                    },
                 })
                 .for_each(|e| {
                     table.add_row(e.to_row());
@@ -219,15 +600,34 @@ fn filter_menu(state: &mut AppState) -> Result<(), std::io::Error> {
                 .interact_text()?
                 .to_lowercase();
 
-            state
-                .get_elements()
+            elements
                 .iter()
+                .filter(|e| !e.is_completed())
                 .filter(|e| {e.get_text().contains(&custom_filter)})
                 .for_each(|e| {
                     table.add_row(e.to_row());
                 });
 
             },
+        2 => { // priority
+            let lowest: Priority = get_priority_from_user(Priority::High)?;
+            let mut matching: Vec<&AppElement> = elements
+                .iter()
+                .filter(|e| !e.is_completed() && e.priority() >= lowest)
+                .collect();
+            matching.sort_by_key(|e| std::cmp::Reverse(e.priority()));
+            matching.iter().for_each(|e| {
+                table.add_row(e.to_row());
+            });
+        },
+        3 => { // completed
+            elements
+                .iter()
+                .filter(|e| e.is_completed())
+                .for_each(|e| {
+                    table.add_row(e.to_row());
+                });
+        },
         _ => ()
     };
     table.printstd();
@@ -235,26 +635,9 @@ fn filter_menu(state: &mut AppState) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-fn edit_menu(state: &mut AppState) -> Result<(), std::io::Error> {
-    state.list();
-    println!("Select the ID of the element to be edited:");
-
-    let id: Option<u16> = get_element_id_from_user(state)?;
-    
-    match id {
-        None => {return Ok(())},
-        _ => ()
-    };
-    
-    let element = state.get_element_by_id(id.unwrap());
-
-    match element {
-        None => {return Ok(())},
-        _ => ()
-    }
-
-    let element = element.unwrap();
-
+/// Questions the user for new values of the given element, returns the
+/// changed element if the user accepts the changes
+fn edit_dialog(element: &AppElement) -> Result<Option<AppElement>, std::io::Error> {
     let disp_due: String = match element.due() {
         Some(due) => {
             let due_timestamp: i64 = due.into();
@@ -312,24 +695,62 @@ fn edit_menu(state: &mut AppState) -> Result<(), std::io::Error> {
         .map(|e| e.to_owned())
         .collect::<Vec<String>>();
 
-    let new_element: AppElement = AppElement::new(id, title, description, due, tags);
+    let (start, end, all_day) = get_event_from_user(Some(element))?;
+
+    let recurrence: Option<Recurrence> = get_recurrence_from_user(element.recurrence())?;
+
+    let priority: Priority = get_priority_from_user(element.priority())?;
+
+    // Fields that are not part of the dialog, like the UUID, stay as they are
+    let mut new_element: AppElement = element.clone();
+    new_element.modify(title, description, due, tags);
+    new_element.set_event(start, end, all_day);
+    new_element.set_priority(priority);
+    new_element.set_recurrence(recurrence);
     println!("\nYou are about to change the element to the following values:\n\n{}\n", new_element);
     if Confirm::new().with_prompt("Do you want to apply these changes?").interact()? {
-        element.modify(
-            new_element.title(),
-            new_element.description(),
-            new_element.due(),
-            new_element.tags()
-        );
-        state.unsynced();
-        return Ok(());
-    } else {
-        return Ok(());
+        return Ok(Some(new_element));
     }
+    Ok(None)
 }
 
-/// Add Dialog
-fn add_menu(state: &mut AppState) -> Result<(), std::io::Error> {
+/// Edit Dialog, returns the handle of the edited element
+fn edit_menu(state: &mut AppState) -> Result<Option<u32>, std::io::Error> {
+    state.list(true);
+    println!("Select the ID of the element to be edited:");
+
+    let handle: Option<u32> = get_element_handle_from_user(state, |_| true)?;
+    
+    let Some(handle) = handle else {return Ok(None)};
+
+    let Some(element) = state.get_element(handle) else {return Ok(None)};
+
+    let Some(new_element) = edit_dialog(element)? else {return Ok(None)};
+    element.modify(
+        new_element.title(),
+        new_element.description(),
+        new_element.due(),
+        new_element.tags()
+    );
+    element.set_event(new_element.start(), new_element.end(), new_element.is_all_day());
+    element.set_priority(new_element.priority());
+    element.set_recurrence(new_element.recurrence().cloned());
+
+    let parent: Option<u32> = get_parent_from_user(state, Some(handle))?;
+    state.set_parent(handle, parent);
+    state.unsynced();
+    Ok(Some(handle))
+}
+
+/// Asks for the parent of a new element and links the element to it
+fn new_element_parent_dialog(state: &mut AppState, element: AppElement) -> Result<AppElement, std::io::Error> {
+    let parent: Option<u32> = get_parent_from_user(state, None)?;
+    let parent: Option<&AppElement> = state.get_elements().iter().find(|e| Some(e.handle()) == parent);
+    Ok(element.with_parent(parent))
+}
+
+/// Questions the user for a new element, returns it if the user accepts it
+fn add_dialog() -> Result<Option<AppElement>, std::io::Error> {
     let title: String = Input::new()
         .with_prompt("Title")
         .allow_empty(true)
@@ -368,39 +789,246 @@ fn add_menu(state: &mut AppState) -> Result<(), std::io::Error> {
         .map(|e| e.to_owned())
         .collect::<Vec<String>>();
 
-    let element: AppElement = AppElement::new(None, title, description, due, tags);
+    let (start, end, all_day) = get_event_from_user(None)?;
+
+    let recurrence: Option<Recurrence> = get_recurrence_from_user(None)?;
+
+    let priority: Priority = get_priority_from_user(Priority::None)?;
+
+    let element: AppElement = AppElement::new(None, title, description, due, tags)
+        .with_event(start, end, all_day)
+        .with_priority(priority)
+        .with_recurrence(recurrence);
     println!("\nYou are about to create the following new element:\n\n{}\n", element);
     if Confirm::new().with_prompt("Do you want to create this element?").interact()? {
+        return Ok(Some(element));
+    }
+    Ok(None)
+}
+
+/// Add Dialog
+fn add_menu(state: &mut AppState) -> Result<(), std::io::Error> {
+    if let Some(element) = add_dialog()? {
+        let element: AppElement = new_element_parent_dialog(state, element)?;
         state.push(Some(element));
         state.unsynced();
-        return Ok(());
+    }
+    Ok(())
+}
+
+/// Remove Dialog, returns the IDs of the removed element and its subtasks that are on the server
+fn remove_menu(state: &mut AppState) -> Result<Vec<u16>, io::Error> {
+    state.list(true);
+    println!("Select the ID of the element to be deleted:");
+
+    if let Some(handle) = get_element_handle_from_user(state, |_| true)? {
+        let subtasks: Vec<u32> = state.subtasks(handle);
+        if !subtasks.is_empty() {
+            let prompt: String = format!("The element has {} subtask(s), they are deleted as well. Continue?", subtasks.len());
+            if !Confirm::new().with_prompt(prompt).interact()? {
+                return Ok(Vec::new());
+            }
+        }
+        let ids: Vec<u16> = subtasks
+            .iter()
+            .chain([handle].iter())
+            .filter_map(|e| state.get_element(*e).and_then(|e| e.id()))
+            .collect();
+        subtasks.into_iter().for_each(|e| {
+            state.remove(e);
+        });
+        if state.remove(handle) {
+            state.unsynced();
+            return Ok(ids);
+        };
+    };
+
+    Ok(Vec::new())
+}
+
+/// Complete or Reopen Dialog, marks an open element as completed or a
+/// completed element as open again
+fn completion_menu(state: &mut AppState, complete: bool) -> Result<(), io::Error> {
+    state.list(!complete);
+    if complete {
+        println!("Select the ID of the element to be completed:");
     } else {
+        println!("Select the ID of the element to be reopened:");
+    }
+
+    let Some(handle) = get_element_handle_from_user(state, |e| e.is_completed() != complete)? else {return Ok(())};
+    state.set_completed(handle, complete);
+
+    let open: Vec<u32> = state
+        .subtasks(handle)
+        .into_iter()
+        .filter(|e| state.get_elements().iter().any(|o| o.handle() == *e && !o.is_completed()))
+        .collect();
+    if complete && !open.is_empty() {
+        let prompt: String = format!("Do you want to complete its {} open subtask(s) as well?", open.len());
+        if Confirm::new().with_prompt(prompt).interact()? {
+            open.into_iter().for_each(|e| {
+                state.set_completed(e, true);
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Config Dialog, changes the config of the running session
+async fn config_menu(state: &mut AppState) -> Result<(), io::Error> {
+    let config: AppConfig = setup_config(state.config())?;
+    let switches_registry: bool = config.server_address != state.config().server_address
+        || config.username != state.config().username;
+
+    if switches_registry && !state.is_synced() {
+        println!("Attention: There are unsynced local changes for the current server!");
+        let selection: usize = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("What do you want to do with them?")
+            .items(&["sync them before switching", "discard them", "cancel"])
+            .default(0)
+            .interact_on_opt(&Term::stderr())?.unwrap_or(2);
+        match selection {
+            0 => {
+                if let Err(e) = state.sync(&mut DialogSyncHandler).await {
+                    println!("Sync failed! {}", e);
+                    println!("Keeping the current config...");
+                    return Ok(());
+                }
+            },
+            1 => println!("Discarding Changes..."),
+            _ => return Ok(()),
+        }
+    }
+
+    println!("Testing the new config...");
+    let mut probe: AppState = AppState::new(config.clone());
+    let works: bool = match probe.test_connection().await {
+        Ok(()) => true,
+        Err(e) => {println!("The new config does not work! {}", e); false},
+    };
+    if !works && !Confirm::new().with_prompt("Do you want to use the new config anyway?").interact()? {
         return Ok(());
     }
+
+    if write_app_config(&config).is_none() {
+        println!("ATTENTION: Config could not be written! Using it for this session only...");
+    }
+    state.set_config(config);
+    println!("Config applied!");
+    Ok(())
 }
 
-/// Remove Dialog
-fn remove_menu(state: &mut AppState) -> Result<(), io::Error> {
-    state.list();
-    println!("Select the ID of the element to be deleted:");
+/// Tells the user about an error, offers to re-enter the credentials if the
+/// server rejected them
+async fn report_error(state: &mut AppState, e: AppError) -> Result<(), io::Error> {
+    println!("Error! {}", e);
+    if let AppError::Auth(_) = e {
+        if Confirm::new().with_prompt("Do you want to re-enter your credentials?").interact()? {
+            config_menu(state).await?;
+        }
+    }
+    Ok(())
+}
 
-    match get_element_id_from_user(state)? {
-        Some(id) => {
-            if state.remove(id) {
-                state.unsynced();
-            };
+/// Tells the user whether a direct change made it to the server
+async fn report_direct_result(state: &mut AppState, result: AppResult<bool>) -> Result<(), io::Error> {
+    match result {
+        Ok(true) => println!("Done! The server accepted the change."),
+        Ok(false) => println!("The server rejected the change, it is kept locally until the next sync."),
+        Err(e) => {
+            println!("The change is kept locally until the next sync.");
+            report_error(state, e).await?;
         },
-        None => {}
-    };
+    }
+    Ok(())
+}
 
+/// Direct Mode Dialog, every change is sent to the server right away
+async fn direct_menu(state: &mut AppState) -> Result<(), io::Error> {
+    println!("Entering Direct Mode...");
+    println!("Every change is sent to the server right away!");
+
+    let commands = ["add", "edit", "remove", "list", "exit"];
+    let mut last_index: usize = commands.len() - 1;
+
+    loop {
+        let (width, _height) = termion::terminal_size().unwrap_or((60, 60));
+        println!("{}", "=".repeat(width as usize));
+        let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("!>")
+            .items(&commands)
+            .default(last_index)
+            .interact_on_opt(&Term::stderr())?.unwrap_or(0);
+        println!("{}", "=".repeat(width as usize));
+        last_index = selection;
+        match selection {
+            0 => {
+                if let Some(element) = add_dialog()? {
+                    let element: AppElement = new_element_parent_dialog(state, element)?;
+                    let result = state.direct_add(element).await;
+                    report_direct_result(state, result).await?;
+                }
+            },
+            1 => {
+                if let Some(handle) = edit_menu(state)? {
+                    match state.get_element(handle).and_then(|e| e.id()) {
+                        Some(id) => {
+                            let result = state.direct_edit(id).await;
+                            report_direct_result(state, result).await?;
+                        },
+                        None => println!("The element is not on the server yet, it is uploaded with the next sync."),
+                    }
+                }
+            },
+            2 => {
+                let ids: Vec<u16> = remove_menu(state)?;
+                if !ids.is_empty() {
+                    // The subtasks are deleted on the server along with the element
+                    let mut result: AppResult<bool> = Ok(true);
+                    for id in ids {
+                        result = state.direct_remove(id).await;
+                        if !matches!(result, Ok(true)) {
+                            break;
+                        }
+                    }
+                    report_direct_result(state, result).await?;
+                }
+            },
+            3 => {
+                roll_over_recurring(state);
+                state.list(false);
+            },
+            4 => {break},
+            _ => (),
+        }
+    }
+    println!("Returning to Normal Mode...");
     Ok(())
 }
 
+/// Asks the user for the ID of an element
+fn get_id_input() -> Result<u16, io::Error> {
+    let input_id: String = Input::new()
+        .with_prompt("ID")
+        .validate_with(|input: &String| {
+            input
+                .parse::<u16>()
+                .is_ok()
+                .then_some(())
+                .ok_or("Invalid format")
+        })
+        .interact_text()?;
+    Ok(input_id.parse::<u16>().unwrap_or(0))
+}
+
+/// Boiling Mode Dialog, every operation is performed on the server directly
+/// without touching the local state
 async fn boiling_menu(state: &mut AppState) -> Result<(), io::Error> {
     println!("Entering Boiling Mode...");
     println!("All chnages are live now!");
 
-    let commands = ["get by id", "exit"];
+    let commands = ["get by id", "list", "filter", "add", "edit", "delete", "exit"];
     let mut last_index: usize = commands.len() - 1;
 
     loop {
@@ -414,23 +1042,58 @@ async fn boiling_menu(state: &mut AppState) -> Result<(), io::Error> {
         println!("{}", "=".repeat(width as usize));
         last_index = selection;
         match selection {
-            0 => {
-                let input_id: String = Input::new()
-                    .with_prompt("ID")
-                    .validate_with(|input: &String| {
-                        input
-                            .parse::<u16>()
-                            .is_ok()
-                            .then_some(())
-                            .ok_or("Invalid format")
-                    })
-                    .interact_text()?;
-                let output: String = state.live_get_by_id(
-                    input_id.parse::<u16>().unwrap()
-                ).await.unwrap_or("Network Communication Error!".to_string()); // TODO: FIXME: unwrap is inappropriate this may fail
-                println!("{}", output);
+            0 => { // get by id
+                match state.live_get_by_id(get_id_input()?).await {
+                    Ok(Some(element)) => println!("{}", element),
+                    Ok(None) => println!("The server does not know this ID!"),
+                    Err(e) => report_error(state, e).await?,
+                }
             },
-            1 => {break},
+            1 | 2 => { // list, filter
+                match state.live_list().await {
+                    Ok(elements) => {
+                        if selection == 1 {
+                            elements_table(elements.iter()).printstd();
+                        } else {
+                            filter_menu(&elements)?;
+                        }
+                    },
+                    Err(e) => report_error(state, e).await?,
+                }
+            },
+            3 => { // add
+                let Some(mut element) = add_dialog()? else {continue};
+                match state.live_add(&mut element).await {
+                    Ok(Some(id)) => println!("Created element {}", id),
+                    Ok(None) => println!("The server rejected the element!"),
+                    Err(e) => report_error(state, e).await?,
+                }
+            },
+            4 => { // edit
+                let element: AppElement = match state.live_get_by_id(get_id_input()?).await {
+                    Ok(Some(element)) => element,
+                    Ok(None) => {println!("The server does not know this ID!"); continue},
+                    Err(e) => {report_error(state, e).await?; continue},
+                };
+                let Some(new_element) = edit_dialog(&element)? else {continue};
+                match state.live_edit(&new_element).await {
+                    Ok(true) => println!("Done!"),
+                    Ok(false) => println!("The server rejected the change!"),
+                    Err(e) => report_error(state, e).await?,
+                }
+            },
+            5 => { // delete
+                let id: u16 = get_id_input()?;
+                if !Confirm::new().with_prompt(format!("Do you really want to delete element {} on the server?", id)).interact()? {
+                    continue;
+                }
+                match state.live_delete(id).await {
+                    Ok(true) => println!("Done!"),
+                    Ok(false) => println!("The server rejected the deletion!"),
+                    Err(e) => report_error(state, e).await?,
+                }
+            },
+            6 => {break},
             _ => (),
         }
     }
@@ -445,7 +1108,9 @@ fn help_menu() {
     println!("You can perform different actions on your calendar");
     println!("Normally all changes you make are Local until you");
     println!("explicitly sync them.");
-    println!("An exception to this is the boiling mode.");
+    println!("An exception to this are the direct mode, which");
+    println!("sends every change to the server right away,");
+    println!("and the boiling mode.");
     println!("In boiling mode all operations are performed");
     println!("live on the Server!");
 }
@@ -455,44 +1120,370 @@ async fn main_menu(config: AppConfig) -> Result<(), io::Error> {
     let mut last_index: usize = 0;
     let mut state: AppState = AppState::new(config);
     let commands: Vec<AppCommand> = AppCommand::get_command_list();
-    
+    load_cached_state(&mut state).await;
+
+    let shared_state: Arc<Mutex<AppState>> = Arc::new(Mutex::new(state));
+    let notices: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let background = tokio::spawn(auto_sync(shared_state.clone(), notices.clone()));
+
     loop {
         let (width, _height) = termion::terminal_size().unwrap_or((60, 60));
+
+        for notice in notices.lock().await.drain(..) {
+            println!("{}", notice);
+        }
+        // The state is not locked while waiting for the selection, so it can
+        // be synced in the background in the meantime. The indicator and the
+        // notices are only refreshed with the next prompt
+        let prompt: String = format!("{}>", shared_state.lock().await.modified_string());
         
         println!("{}", "=".repeat(width as usize));
         let selection: usize = FuzzySelect::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("{}>", state.modified_string()))
+            .with_prompt(prompt)
             .items(&commands)
             .default(last_index)
             .interact_on_opt(&Term::stderr())?.unwrap_or(0);
         println!("{}", "=".repeat(width as usize));
 
         last_index = selection;
+        let mut state = shared_state.lock().await;
         match AppCommand::from(selection) {
-                AppCommand::List => state.list(),
-                AppCommand::Sync => state.sync().await.unwrap(),
-                AppCommand::Filter => filter_menu(&mut state)?,
-                AppCommand::Edit => edit_menu(&mut state)?,
+                AppCommand::List => {
+                    roll_over_recurring(&mut state);
+                    state.list(false);
+                },
+                AppCommand::Sync => {
+                    match state.sync(&mut DialogSyncHandler).await {
+                        Ok(_) => (),
+                        Err(AppError::Aborted) => println!("{}", AppError::Aborted),
+                        Err(e) => {
+                            println!("Sync failed, the local changes are kept.");
+                            report_error(&mut state, e).await?;
+                        },
+                    }
+                },
+                AppCommand::Filter => {
+                    roll_over_recurring(&mut state);
+                    filter_menu(state.get_elements())?;
+                },
+                AppCommand::Edit => {edit_menu(&mut state)?;},
                 AppCommand::Add => add_menu(&mut state)?,
-                AppCommand::Remove => remove_menu(&mut state)?,
+                AppCommand::Remove => {remove_menu(&mut state)?;},
+                AppCommand::Complete => completion_menu(&mut state, true)?,
+                AppCommand::Reopen => completion_menu(&mut state, false)?,
+                AppCommand::Direct => direct_menu(&mut state).await?,
                 AppCommand::Boiling => boiling_menu(&mut state).await?,
+                AppCommand::Config => config_menu(&mut state).await?,
                 AppCommand::Help => help_menu(),
                 AppCommand::Quit => break,
                 _ => {println!("Not yet implemented")}
             }
+        write_app_cache(&state.to_cache());
     }
+    let mut state = shared_state.lock().await;
+    background.abort();
     if !state.is_synced() {
         if Confirm::new().with_prompt("Attention: The current state seems to be unsynced with the server! Do you want to sync now?").interact()? {
             println!("Syncing...");
-            state.sync().await.unwrap_or(());
+            if let Err(e) = state.sync(&mut DialogSyncHandler).await {
+                println!("Sync failed! {}", e);
+                println!("Keeping unsynced changes in the local cache...");
+            }
         } else {
-            println!("Discarding Changes...");
+            println!("Keeping unsynced changes in the local cache...");
         }
     }
+    write_app_cache(&state.to_cache());
     println!("Bye!");
     Ok(())
 }
 
+/// Parses the due date given on the command line, 'none' removes the due date
+fn parse_due_arg(input: &str) -> Result<Option<u32>, String> {
+    if input.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    parse_datetime_input(input)
+        .map(Some)
+        .ok_or(format!("Invalid format '{}', expected e.g. '+1', '-1' or '04.06.23 19:00'", input))
+}
+
+/// Parses the duration of an event given on the command line
+fn parse_duration_arg(input: &str) -> Result<u32, String> {
+    parse_duration_input(input).ok_or(format!("Invalid duration '{}', expected e.g. '90m', '1h30m' or '2d'", input))
+}
+
+/// Returns the event times of the command line combined with the current ones
+/// of the element, None if none were given. A new start moves the whole
+/// event, keeping its length
+fn event_from_args(args: &ArgMatches, current: Option<&AppElement>) -> AppResult<Option<EventTimes>> {
+    let start_arg: Option<Option<u32>> = args.try_get_one::<Option<u32>>("start").ok().flatten().copied();
+    let end_arg: Option<Option<u32>> = args.try_get_one::<Option<u32>>("end").ok().flatten().copied();
+    let duration: Option<u32> = args.try_get_one::<u32>("duration").ok().flatten().copied();
+    let all_day_arg: Option<bool> = args.try_get_one::<bool>("all-day").ok().flatten().copied();
+    if start_arg.is_none() && end_arg.is_none() && duration.is_none() && all_day_arg.is_none() {
+        return Ok(None);
+    }
+
+    let (current_start, current_end, current_all_day) = current
+        .map(|e| (e.start(), e.end(), e.is_all_day()))
+        .unwrap_or_default();
+    let start: Option<u32> = start_arg.unwrap_or(current_start);
+    let all_day: bool = all_day_arg.unwrap_or(current_all_day);
+    let end: Option<u32> = match (start, duration, end_arg) {
+        (None, _, _) if start_arg.is_none() => return Err(AppError::InvalidEvent("it needs a start".to_string())),
+        (None, _, _) => None,
+        (Some(start), Some(duration), _) => Some(event_end(start, duration, all_day)),
+        (Some(_), None, Some(end)) => end,
+        (Some(start), None, None) => current_end
+            .zip(current_start)
+            .map(|(end, current_start)| start.saturating_add(end.saturating_sub(current_start))),
+    };
+    Ok(Some((start, end, all_day)))
+}
+
+/// Parses the recurrence rule given on the command line, 'none' removes the rule
+fn parse_repeat_arg(input: &str) -> Result<Option<Recurrence>, String> {
+    if input.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    input.parse().map(Some)
+}
+
+/// The arguments describing the fields of an element, used by add and edit
+fn element_args(title_required: bool) -> Vec<Arg> {
+    vec![
+        Arg::new("title")
+            .short('t')
+            .long("title")
+            .required(title_required)
+            .help("Title of the element"),
+        Arg::new("description")
+            .short('d')
+            .long("description")
+            .help("Description of the element"),
+        Arg::new("due")
+            .long("due")
+            .allow_hyphen_values(true)
+            .value_parser(parse_due_arg)
+            .help("Number of days (e.g. '+1', '-1'), a full date with time (e.g. '04.06.23 19:00') or 'none'"),
+        Arg::new("start")
+            .long("start")
+            .allow_hyphen_values(true)
+            .value_parser(parse_due_arg)
+            .help("When the element starts as an event, in the same format as --due, 'none' makes it a task again"),
+        Arg::new("end")
+            .long("end")
+            .allow_hyphen_values(true)
+            .value_parser(parse_due_arg)
+            .conflicts_with("duration")
+            .help("When the event ends, the last day of all-day events, or 'none'"),
+        Arg::new("duration")
+            .long("duration")
+            .value_parser(parse_duration_arg)
+            .help("How long the event takes, e.g. '90m', '1h30m' or '2d'"),
+        Arg::new("all-day")
+            .long("all-day")
+            .num_args(0..=1)
+            .default_missing_value("true")
+            .value_parser(clap::value_parser!(bool))
+            .help("Whether the event takes whole days, 'false' gives it times again"),
+        Arg::new("tags")
+            .long("tags")
+            .num_args(0..)
+            .help("Tags of the element, passing no tags clears them"),
+        Arg::new("priority")
+            .short('p')
+            .long("priority")
+            .value_parser(|e: &str| e.parse::<Priority>())
+            .help("Priority of the element: none, low, medium or high"),
+        Arg::new("repeat")
+            .long("repeat")
+            .value_parser(parse_repeat_arg)
+            .help("How the element repeats, e.g. 'weekly', 'FREQ=MONTHLY;INTERVAL=3;COUNT=4' or 'none'"),
+        Arg::new("parent")
+            .long("parent")
+            .help("ID of the element this one is a subtask of, or 'none'"),
+    ]
+}
+
+/// The flag that lets a subcommand work on the subtasks of an element as well
+fn subtasks_arg() -> Arg {
+    Arg::new("with-subtasks")
+        .short('r')
+        .long("with-subtasks")
+        .action(ArgAction::SetTrue)
+        .help("Include the subtasks of the element")
+}
+
+/// The positional id argument used by subcommands working on a single element
+fn id_arg() -> Arg {
+    Arg::new("id")
+        .required(true)
+        .help("ID of the element, or e.g. 'new1' for an element that is not synced yet")
+}
+
+/// Runs a single subcommand without any dialogs, using the same state
+/// operations as the interactive menus. Works on the cached state if the
+/// server can not be reached or `offline` is set
+async fn run_subcommand(config: AppConfig, name: &str, args: &ArgMatches) -> AppResult<()> {
+    let mut state: AppState = AppState::new(config);
+    let mut handler = FixedSyncHandler {
+        resolution: match args.get_one::<String>("on-conflict").map(|e| e.as_str()) {
+            Some("local") => Resolution::Local,
+            _ => Resolution::Remote,
+        },
+        dry_run: args.try_get_one::<bool>("dry-run").ok().flatten().copied().unwrap_or(false),
+    };
+    let offline: bool = args.get_flag("offline");
+    // Listing only takes over the changes of the server, the local changes
+    // are left for an explicit sync
+    let read_only: bool = matches!(name, "list" | "show");
+
+    if let Some(cache) = obtain_app_cache() {
+        state.load_cache(cache);
+    }
+    attach_journal(&mut state);
+    roll_over_recurring(&mut state);
+
+    if read_only && !offline {
+        if let Err(e) = state.refresh().await {
+            eprintln!("Fetching failed! {}", e);
+            eprintln!("Working with the cached elements...");
+        }
+    } else if name != "add" && !offline {
+        if let Err(e) = state.sync(&mut handler).await {
+            if name == "sync" {
+                if handler.dry_run && matches!(e, AppError::Aborted) {
+                    println!("Dry run, nothing was sent to the server");
+                    return Ok(());
+                }
+                return Err(e);
+            }
+            eprintln!("Sync failed! {}", e);
+            eprintln!("Working with the cached elements...");
+        }
+    }
+
+    let title: Option<String> = args.try_get_one::<String>("title").ok().flatten().cloned();
+    let description: Option<String> = args.try_get_one::<String>("description").ok().flatten().cloned();
+    let due: Option<Option<u32>> = args.try_get_one::<Option<u32>>("due").ok().flatten().copied();
+    let tags: Option<Vec<String>> = args
+        .try_get_many::<String>("tags")
+        .ok()
+        .flatten()
+        .map(|e| e.cloned().collect())
+        .or_else(|| args.try_contains_id("tags").unwrap_or(false).then(Vec::new));
+    let priority: Option<Priority> = args.try_get_one::<Priority>("priority").ok().flatten().copied();
+    let repeat: Option<Option<Recurrence>> = args.try_get_one::<Option<Recurrence>>("repeat").ok().flatten().cloned();
+    let label: String = args.try_get_one::<String>("id").ok().flatten().cloned().unwrap_or_default();
+    let handle: u32 = state.find_handle(&label).unwrap_or(0);
+    let with_subtasks: bool = args.try_get_one::<bool>("with-subtasks").ok().flatten().copied().unwrap_or(false);
+    let parent: Option<Option<u32>> = match args.try_get_one::<String>("parent").ok().flatten() {
+        Some(parent) if parent.eq_ignore_ascii_case("none") => Some(None),
+        Some(parent) => Some(Some(state.find_handle(parent).ok_or(AppError::InvalidParent(parent.clone()))?)),
+        None => None,
+    };
+
+    match name {
+        "list" => {
+            if args.try_get_one::<String>("sort").ok().flatten().map(|e| e.as_str()) == Some("priority") {
+                state.sort_by_priority();
+            }
+            state.list(args.try_get_one::<bool>("all").ok().flatten().copied().unwrap_or(false));
+        },
+        "show" => {
+            let element = state
+                .get_element(handle)
+                .ok_or(AppError::UnknownElement(label))?;
+            println!("{}", element);
+        },
+        "add" => {
+            let mut element: AppElement = AppElement::new(
+                None,
+                title.unwrap_or_default(),
+                description.unwrap_or_default(),
+                due.flatten(),
+                tags.unwrap_or_default(),
+            )
+            .with_priority(priority.unwrap_or_default())
+            .with_recurrence(repeat.flatten())
+            .with_parent(state.get_elements().iter().find(|e| Some(e.handle()) == parent.flatten()));
+            if let Some((start, end, all_day)) = event_from_args(args, None)? {
+                if !element.set_event(start, end, all_day) {
+                    return Err(AppError::InvalidEvent("it ends before it starts".to_string()));
+                }
+            }
+            state.push(Some(element));
+            state.unsynced();
+        },
+        "edit" => {
+            let event = event_from_args(args, state.get_elements().iter().find(|e| e.handle() == handle))?;
+            let element = state
+                .get_element(handle)
+                .ok_or(AppError::UnknownElement(label))?;
+            element.modify(
+                title.unwrap_or(element.title()),
+                description.unwrap_or(element.description()),
+                due.unwrap_or(element.due()),
+                tags.unwrap_or(element.tags()),
+            );
+            if let Some((start, end, all_day)) = event {
+                if !element.set_event(start, end, all_day) {
+                    return Err(AppError::InvalidEvent("it ends before it starts".to_string()));
+                }
+            }
+            element.set_priority(priority.unwrap_or(element.priority()));
+            if let Some(repeat) = repeat {
+                element.set_recurrence(repeat);
+            }
+            if let Some(parent) = parent {
+                if !state.set_parent(handle, parent) {
+                    return Err(AppError::InvalidParent(args.get_one::<String>("parent").cloned().unwrap_or_default()));
+                }
+            }
+            state.unsynced();
+        },
+        "rm" => {
+            let subtasks: Vec<u32> = state.subtasks(handle);
+            if !subtasks.is_empty() && !with_subtasks {
+                return Err(AppError::HasSubtasks(label));
+            }
+            if !state.remove(handle) {
+                return Err(AppError::UnknownElement(label));
+            }
+            subtasks.into_iter().for_each(|e| {
+                state.remove(e);
+            });
+            state.unsynced();
+        },
+        "complete" | "reopen" => {
+            if state.get_element(handle).is_none() {
+                return Err(AppError::UnknownElement(label));
+            }
+            state.set_completed(handle, name == "complete");
+            if with_subtasks {
+                state.subtasks(handle).into_iter().for_each(|e| {
+                    state.set_completed(e, name == "complete");
+                });
+            }
+        },
+        _ => (),
+    }
+
+    let result: AppResult<()> = if !state.is_synced() && !offline && !read_only {
+        let result = state.sync(&mut handler).await.map(|_| ());
+        if result.is_err() {
+            eprintln!("The changes are kept in the local cache");
+        }
+        result
+    } else {
+        Ok(())
+    };
+    write_app_cache(&state.to_cache());
+    result
+}
+
 #[tokio::main]
 async fn main() {
     let args: ArgMatches = Command::new("Freemind CLI")
@@ -511,6 +1502,70 @@ async fn main() {
             .action(ArgAction::SetTrue)
             .help("Skip loading and saving of the configuration file")
         )
+        .arg(Arg::new("on-conflict")
+            .long("on-conflict")
+            .global(true)
+            .value_parser(["local", "server"])
+            .default_value("server")
+            .help("Which version to keep when a subcommand runs into a sync conflict")
+        )
+        .arg(Arg::new("offline")
+            .long("offline")
+            .global(true)
+            .action(ArgAction::SetTrue)
+            .help("Only work on the local cache when running a subcommand, without syncing")
+        )
+        .subcommand(Command::new("add")
+            .about("Create a new element and sync it")
+            .args(element_args(true))
+        )
+        .subcommand(Command::new("list")
+            .about("Fetch and list all elements without uploading local changes, falls back to the cache when offline")
+            .arg(Arg::new("all")
+                .short('a')
+                .long("all")
+                .action(ArgAction::SetTrue)
+                .help("Also list completed elements")
+            )
+            .arg(Arg::new("sort")
+                .long("sort")
+                .value_parser(["due", "priority"])
+                .default_value("due")
+                .help("Order of the listed elements")
+            )
+        )
+        .subcommand(Command::new("edit")
+            .about("Change the given fields of an element and sync it")
+            .arg(id_arg())
+            .args(element_args(false))
+        )
+        .subcommand(Command::new("rm")
+            .about("Remove an element and sync")
+            .arg(id_arg())
+            .arg(subtasks_arg())
+        )
+        .subcommand(Command::new("complete")
+            .about("Mark an element as completed and sync")
+            .arg(id_arg())
+            .arg(subtasks_arg())
+        )
+        .subcommand(Command::new("reopen")
+            .about("Mark a completed element as open again and sync")
+            .arg(id_arg())
+            .arg(subtasks_arg())
+        )
+        .subcommand(Command::new("sync")
+            .about("Sync with the server")
+            .arg(Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Only show the changes that would be sent to the server")
+            )
+        )
+        .subcommand(Command::new("show")
+            .about("Fetch and show a single element without uploading local changes, falls back to the cache when offline")
+            .arg(id_arg())
+        )
         .get_matches();
 
     let config_setup: &bool = args.get_one("config").unwrap_or(&false);
@@ -522,6 +1577,11 @@ async fn main() {
             .expect("FATAL! Failed to create or read config! (tried under '~/.config/freemind/freemind-cli.config')\nRun with `--skip-config-load` to avoid this issue, or fix your file permissions!");
     }
 
+    if args.subcommand().is_some() && !config_setup && (config.is_default() || config.is_empty()) {
+        eprintln!("No usable config found! Run `--config` once to set it up.");
+        std::process::exit(1);
+    }
+
     if *config_setup || config.is_default() || config.is_empty() {
         println!("Config could not be read, found or was skipped.\nEntering Configuration Setup:");
         config = setup_config(&config).expect("FATAL! Setup Dailog encountered an error!");
@@ -533,7 +1593,18 @@ async fn main() {
     }
 
     // Config is now initialized! Now Deal with it.
-    
+
+    if let Some((name, sub_args)) = args.subcommand() {
+        if let Err(e) = run_subcommand(config, name, sub_args).await {
+            eprintln!("Error: {}", e);
+            if let AppError::Auth(_) = e {
+                eprintln!("Run with `--config` to update your credentials.");
+            }
+            std::process::exit(1);
+        }
+        return;
+    }
+
     main_menu(config).await.expect("FATAL! Dialog encountered an error!");
 
 
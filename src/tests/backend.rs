@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+use crate::data::data_types::{AppElement, AppState};
+use super::{KeepLocal, state_for, titles};
+
+/// Returns a registry file path that no other test uses
+fn registry_path() -> PathBuf {
+    std::env::temp_dir().join(format!("freemind-cli-test-{}.xml", rand::random::<u64>()))
+}
+
+#[tokio::test]
+async fn memory_backend_keeps_synced_changes() {
+    let mut state: AppState = state_for("memory://");
+
+    state.push(Some(AppElement::new(None, "First".to_string(), String::new(), None, Vec::new())));
+    state.push(Some(AppElement::new(None, "Second".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let id: u16 = state.get_elements().iter().find(|e| e.title() == "First").unwrap().id().unwrap();
+    state.remove(state.find_handle(&id.to_string()).unwrap());
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let remote: Vec<AppElement> = state.live_list().await.unwrap();
+    assert_eq!(remote.iter().map(|e| e.title()).collect::<Vec<String>>(), vec!["Second"]);
+    assert!(state.live_get_by_id(id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn file_backend_is_shared_between_states() {
+    let path: PathBuf = registry_path();
+    let address: String = format!("file://{}", path.display());
+    let mut first: AppState = state_for(&address);
+    let mut second: AppState = state_for(&address);
+
+    first.push(Some(AppElement::new(None, "Shared".to_string(), String::new(), None, Vec::new())));
+    first.unsynced();
+    first.sync(&mut KeepLocal).await.unwrap();
+    second.sync(&mut KeepLocal).await.unwrap();
+    assert_eq!(titles(&second), vec!["Shared"]);
+
+    let id: u16 = second.get_elements()[0].id().unwrap();
+    second.get_element_by_id(id).unwrap().modify("Edited".to_string(), String::new(), None, Vec::new());
+    assert!(second.direct_edit(id).await.unwrap());
+    first.sync(&mut KeepLocal).await.unwrap();
+    assert_eq!(titles(&first), vec!["Edited"]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn file_backend_keeps_unknown_fields() {
+    let path: PathBuf = registry_path();
+    std::fs::write(
+        &path,
+        "<registry><entry id=\"7\"><name>Old</name><description/><tags/><color>red</color></entry></registry>",
+    ).unwrap();
+    let mut state: AppState = state_for(&format!("file://{}", path.display()));
+
+    state.sync(&mut KeepLocal).await.unwrap();
+    state.get_element_by_id(7).unwrap().modify("New".to_string(), String::new(), None, Vec::new());
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let xml: String = std::fs::read_to_string(&path).unwrap();
+    assert!(xml.contains("<name>New</name>"));
+    assert!(xml.contains("<color>red</color>"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn file_backend_waits_for_the_lock() {
+    let path: PathBuf = registry_path();
+    let lock: PathBuf = PathBuf::from(format!("{}.lock", path.display()));
+    let mut state: AppState = state_for(&format!("file://{}", path.display()));
+
+    std::fs::write(&lock, "").unwrap();
+    let held_lock: PathBuf = lock.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        std::fs::remove_file(held_lock).unwrap();
+    });
+    assert!(state.direct_add(AppElement::new(None, "Waited".to_string(), String::new(), None, Vec::new())).await.unwrap());
+
+    assert!(std::fs::read_to_string(&path).unwrap().contains("<name>Waited</name>"));
+    assert!(!lock.exists());
+    assert!(!PathBuf::from(format!("{}.tmp", path.display())).exists());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn file_backend_removes_stale_locks() {
+    let path: PathBuf = registry_path();
+    let lock: PathBuf = PathBuf::from(format!("{}.lock", path.display()));
+    let mut state: AppState = state_for(&format!("file://{}", path.display()));
+
+    let lock_file: std::fs::File = std::fs::File::create(&lock).unwrap();
+    lock_file.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(120)).unwrap();
+    state.push(Some(AppElement::new(None, "Stale".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    assert!(std::fs::read_to_string(&path).unwrap().contains("<name>Stale</name>"));
+    assert!(!lock.exists());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+use crate::data::data_types::{AppCache, AppElement, AppState, Freshness, Priority};
+use super::{KeepLocal, local, state_for, titles};
+
+/// Returns a file path with the given extension that no other test uses
+fn temp_path(extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("freemind-cli-test-{}.{}", rand::random::<u64>(), extension))
+}
+
+#[tokio::test]
+async fn cached_state_survives_writing_and_reading_the_cache_file() {
+    let (registry, cache, rewritten) = (temp_path("xml"), temp_path("cache"), temp_path("cache"));
+    let address: String = format!("file://{}", registry.display());
+    let mut state: AppState = state_for(&address);
+    state.push(Some(
+        AppElement::new(None, "Move".to_string(), "boxes".to_string(), None, vec!["home".to_string()])
+            .with_priority(Priority::High)
+            .with_recurrence(Some("FREQ=MONTHLY;BYMONTHDAY=31".parse().unwrap()))
+            .with_event(Some(local(2099, 1, 31, 9, 0)), Some(local(2099, 1, 31, 17, 0)), false),
+    ));
+    state.push(Some(AppElement::new(None, "Old".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    // Local changes of every kind are pending when the cache is written
+    let moving: u32 = state.find_handle(&state.get_elements()[0].id().unwrap().to_string()).unwrap();
+    let old: u32 = state.get_labels(|e| e.title() == "Old")[0].0;
+    state.get_element(moving).unwrap().modify("Move out".to_string(), "boxes".to_string(), None, vec!["home".to_string()]);
+    state.remove(old);
+    let parent: Option<&AppElement> = state.get_elements().iter().find(|e| e.handle() == moving);
+    let subtask: AppElement = AppElement::new(None, "Pack".to_string(), String::new(), None, Vec::new()).with_parent(parent);
+    state.push(Some(subtask));
+    state.unsynced();
+    let labels: Vec<(u32, String)> = state.get_labels(|_| true);
+
+    confy::store_path(&cache, state.to_cache()).unwrap();
+    let mut loaded: AppState = state_for(&address);
+    assert!(loaded.load_cache(confy::load_path::<AppCache>(&cache).unwrap()));
+
+    // Writing the loaded state again gives the same file
+    confy::store_path(&rewritten, loaded.to_cache()).unwrap();
+    assert_eq!(std::fs::read_to_string(&cache).unwrap(), std::fs::read_to_string(&rewritten).unwrap());
+    assert_eq!(loaded.get_labels(|_| true), labels);
+    assert_eq!(loaded.last_sync(), state.last_sync());
+    assert!(!loaded.is_synced());
+    assert_eq!(loaded.subtasks(moving), vec![loaded.find_handle(&labels[1].1).unwrap()]);
+    assert_eq!(loaded.check_freshness().await.unwrap(), Freshness::Fresh);
+
+    // The pending changes are still uploaded
+    loaded.sync(&mut KeepLocal).await.unwrap();
+    let remote: Vec<AppElement> = loaded.live_list().await.unwrap();
+    let mut remote_titles: Vec<String> = remote.iter().map(|e| e.title()).collect();
+    remote_titles.sort();
+    assert_eq!(remote_titles, titles(&loaded));
+    assert_eq!(remote_titles, vec!["Move out", "Pack"]);
+
+    for path in [registry, cache, rewritten] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+use crate::data::data_types::{AppElement, AppState};
+use super::mock::{entry_xml, MockServer};
+use super::{KeepLocal, local, memory_state};
+
+fn event(title: &str, start: u32, end: Option<u32>, all_day: bool) -> AppElement {
+    AppElement::new(None, title.to_string(), String::new(), None, Vec::new()).with_event(Some(start), end, all_day)
+}
+
+#[tokio::test]
+async fn event_times_are_synced_both_ways() {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    server.set_registry(&format!("<registry>{}</registry>", entry_xml(1, "Standup", "")));
+    let mut state: AppState = AppState::new(server.config());
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let (start, end) = (local(2099, 3, 2, 0, 0), local(2099, 3, 4, 0, 0));
+    state.push(Some(event("Trip", local(2099, 3, 2, 15, 0), Some(local(2099, 3, 4, 9, 0)), true)));
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let id: u16 = state.get_elements().iter().find(|e| e.title() == "Trip").unwrap().id().unwrap();
+    let entry: String = server.entry(id).unwrap();
+    assert!(entry.contains(&format!("<start>{}</start><end>{}</end><allday>true</allday>", start, end)));
+    assert!(!server.entry(1).unwrap().contains("<start>"));
+
+    let meeting: u32 = local(2099, 3, 5, 10, 0);
+    server.put_entry(&entry_xml(1, "Standup", "").replace(
+        "<tags>",
+        &format!("<start>{}</start><end>{}</end><tags>", meeting, meeting + 900),
+    ));
+    state.sync(&mut KeepLocal).await.unwrap();
+    let standup: &AppElement = state.get_element_by_id(1).unwrap();
+    assert_eq!((standup.start(), standup.end(), standup.is_all_day()), (Some(meeting), Some(meeting + 900), false));
+    assert!(standup.to_string().contains("When: Thu, 05 Mar 2099 10:00 - 10:15 (15m)"));
+}
+
+#[test]
+fn all_day_events_take_whole_days() {
+    let trip: AppElement = event("Trip", local(2099, 3, 2, 15, 0), Some(local(2099, 3, 4, 9, 0)), true);
+
+    assert_eq!(trip.start(), Some(local(2099, 3, 2, 0, 0)));
+    assert_eq!(trip.over_at(), Some(local(2099, 3, 5, 0, 0)));
+    assert!(trip.overlaps(local(2099, 3, 4, 23, 0), local(2099, 3, 6, 0, 0)));
+    assert!(!trip.overlaps(local(2099, 3, 5, 0, 0), local(2099, 3, 6, 0, 0)));
+    assert!(trip.to_string().contains("When: Mon, 02 Mar 2099 - Wed, 04 Mar 2099 (3 days)"));
+}
+
+#[test]
+fn events_can_not_end_before_they_start() {
+    let start: u32 = local(2099, 3, 2, 10, 0);
+    let mut meeting: AppElement = event("Meeting", start, Some(start + 3600), false);
+
+    assert!(!meeting.set_event(Some(start), Some(start - 60), false));
+    assert_eq!(meeting.end(), Some(start + 3600));
+    assert!(meeting.set_event(None, Some(start), true));
+    assert!(!meeting.is_event() && !meeting.is_all_day() && meeting.end().is_none());
+    assert_eq!(event("Meeting", start, Some(start - 60), false).end(), None);
+}
+
+#[test]
+fn recurring_events_keep_their_length() {
+    let mut state: AppState = memory_state();
+    let start: u32 = local(2099, 3, 2, 10, 0);
+    state.push(Some(event("Review", start, Some(start + 5400), false).with_recurrence(Some("weekly".parse().unwrap()))));
+
+    assert!(state.set_completed(state.find_handle("new1").unwrap(), true));
+
+    let next: &AppElement = state.get_elements().iter().find(|e| !e.is_completed()).unwrap();
+    assert_eq!(next.start(), Some(local(2099, 3, 9, 10, 0)));
+    assert_eq!(next.end(), Some(local(2099, 3, 9, 11, 30)));
+    assert_eq!(next.due(), None);
+}
+
+#[test]
+fn durations_are_parsed() {
+    assert_eq!(crate::parse_duration_input("90m"), Some(5400));
+    assert_eq!(crate::parse_duration_input("1h 30m"), Some(5400));
+    assert_eq!(crate::parse_duration_input("2d"), Some(172800));
+    assert_eq!(crate::parse_duration_input("90"), None);
+    assert_eq!(crate::parse_duration_input("1w"), None);
+    assert_eq!(crate::parse_duration_input(""), None);
+}
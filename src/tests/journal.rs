@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+use crate::data::data_types::{AppCache, AppElement, AppState, Journal};
+use crate::error::AppError;
+use super::mock::{MockServer, entry_xml};
+use super::KeepLocal;
+
+/// Returns a journal path that no other test uses
+fn journal_path() -> PathBuf {
+    std::env::temp_dir().join(format!("freemind-cli-test-{}.journal", rand::random::<u64>()))
+}
+
+/// Starts a server with two entries and a synced state, then makes local
+/// changes and lets their upload fail. Returns the cache as it was before
+/// the upload, like a crash right after the upload would leave it
+async fn interrupted_upload(path: &Path) -> (MockServer, AppCache) {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    server.set_registry(&format!(
+        "<registry>{}{}</registry>",
+        entry_xml(1, "First", "one"),
+        entry_xml(2, "Second", "two"),
+    ));
+    let mut state: AppState = AppState::new(server.config());
+    assert_eq!(state.attach_journal(Journal::open(path.to_path_buf())), 0);
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    state.get_element_by_id(1).unwrap().modify("Changed".to_string(), "one".to_string(), None, Vec::new());
+    state.remove(state.find_handle("2").unwrap());
+    state.push(Some(AppElement::new(None, "Third".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    let cache: AppCache = state.to_cache();
+
+    server.fail_next("/xml/changes", 500);
+    assert!(state.sync(&mut KeepLocal).await.is_err());
+    assert!(path.exists());
+    (server, cache)
+}
+
+/// Starts the app again from the given cache and journal and syncs
+async fn restart(server: &MockServer, cache: AppCache, path: &Path) -> AppState {
+    let mut state: AppState = AppState::new(server.config());
+    state.load_cache(cache);
+    assert_eq!(state.attach_journal(Journal::open(path.to_path_buf())), 3);
+    assert!(!state.is_synced());
+    state.sync(&mut KeepLocal).await.unwrap();
+    state
+}
+
+fn assert_synced(server: &MockServer, state: &AppState, path: &Path) {
+    let mut titles: Vec<String> = state.get_elements().iter().map(|e| e.title()).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Changed", "Third"]);
+    assert_eq!(server.ids().len(), 2);
+    assert!(server.entry(1).unwrap().contains("<name>Changed</name>"));
+    assert!(server.entry(2).is_none());
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn operations_the_server_did_not_get_are_sent_again() {
+    let path: PathBuf = journal_path();
+    let (server, cache) = interrupted_upload(&path).await;
+
+    let state: AppState = restart(&server, cache, &path).await;
+
+    assert_synced(&server, &state, &path);
+}
+
+#[tokio::test]
+async fn operations_the_server_got_are_not_applied_twice() {
+    let path: PathBuf = journal_path();
+    let (server, cache) = interrupted_upload(&path).await;
+
+    // The server applied the upload, but the answer never arrived
+    let journal: String = std::fs::read_to_string(&path).unwrap();
+    let third: &str = journal
+        .split("<op ")
+        .find(|e| e.contains("kind=\"create\""))
+        .unwrap();
+    let id: u16 = third
+        .split("<entry id=\"")
+        .nth(1)
+        .and_then(|e| e.split('"').next())
+        .and_then(|e| e.parse().ok())
+        .unwrap();
+    let uuid: &str = third.split("uuid=\"").nth(1).and_then(|e| e.split('"').next()).unwrap();
+    server.put_entry(&entry_xml(1, "Changed", "one"));
+    server.put_entry(&entry_xml(id, "Third", "").replacen("\">", &format!("\" uuid=\"{}\">", uuid), 1));
+    server.delete_entry(2);
+
+    let state: AppState = restart(&server, cache, &path).await;
+
+    assert_synced(&server, &state, &path);
+    assert!(server.ids().contains(&id));
+}
+
+#[tokio::test]
+async fn given_up_attempts_to_create_an_element_are_not_replayed() {
+    let path: PathBuf = journal_path();
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    server.set_registry(&format!("<registry>{}</registry>", entry_xml(1, "First", "one")));
+    let mut state: AppState = AppState::new(server.config());
+    state.attach_journal(Journal::open(path.clone()));
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    state.push(Some(AppElement::new(None, "Second".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    let cache: AppCache = state.to_cache();
+    // Every attempt is rejected, as another client changes the registry in between
+    for attempt in 0..3 {
+        server.interleave("/xml/fetch", &entry_xml(1, "First", &attempt.to_string()));
+    }
+    assert!(matches!(state.sync(&mut KeepLocal).await, Err(AppError::RegistryChanged)));
+    assert_eq!(std::fs::read_to_string(&path).unwrap().matches("kind=\"create\"").count(), 1);
+
+    let mut state: AppState = AppState::new(server.config());
+    state.load_cache(cache);
+    assert_eq!(state.attach_journal(Journal::open(path.clone())), 1);
+    let report = state.sync(&mut KeepLocal).await.unwrap();
+
+    assert!(report.revived.is_empty());
+    assert_eq!(server.ids().len(), 2);
+    assert_eq!(state.get_elements().len(), 2);
+    assert!(!path.exists());
+}
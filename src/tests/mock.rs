@@ -0,0 +1,311 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use hyper::{Body, Request, Response, Server, service::{make_service_fn, service_fn}};
+use crate::data::data_types::{AppConfig, AuthMethod};
+
+/// An in-process Freemind server holding the registry in memory.
+/// Serves the same endpoints as the real server and checks the
+/// `user` and `token`/`password` headers of every request
+pub struct MockServer {
+    address: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+struct MockState {
+    user: String,
+    secret: String,
+    /// The entries of the registry as raw XML, keyed by their ID
+    entries: Vec<(u16, String)>,
+    /// Endpoints that fail with the given status on their next request
+    failures: Vec<(String, u16)>,
+    /// Endpoints whose next request is handled, but answered with a timeout
+    lost: Vec<String>,
+    /// Whether change sets are accepted, like newer servers do
+    changes: bool,
+    /// Whether the registry is sent with an ETag and uploads are checked against it
+    etags: bool,
+    /// Counts the changes of the registry, used as its ETag
+    revision: u32,
+    /// Entries another client writes right after the next request to the endpoint
+    interleaved: Vec<(String, String)>,
+    /// The paths of all requests in the order they arrived
+    requests: Vec<String>,
+}
+
+impl MockServer {
+    /// Starts a server on a random local port, accepting the given credentials
+    pub async fn start(user: &str, secret: &str) -> Self {
+        let state: Arc<Mutex<MockState>> = Arc::new(Mutex::new(MockState {
+            user: user.to_string(),
+            secret: secret.to_string(),
+            entries: Vec::new(),
+            failures: Vec::new(),
+            lost: Vec::new(),
+            changes: true,
+            etags: true,
+            revision: 0,
+            interleaved: Vec::new(),
+            requests: Vec::new(),
+        }));
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req)))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let address: String = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        Self {
+            address,
+            state,
+        }
+    }
+
+    /// Returns a config that authenticates against this server
+    pub fn config(&self) -> AppConfig {
+        let state = self.state.lock().unwrap();
+        AppConfig::new(self.address.clone(), state.user.clone(), state.secret.clone(), AuthMethod::Token)
+    }
+
+    /// Replaces the whole registry
+    pub fn set_registry(&self, xml: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.entries = parse_entries(xml);
+        state.revision += 1;
+    }
+
+    /// Returns the IDs of all entries in the registry
+    pub fn ids(&self) -> Vec<u16> {
+        self.state.lock().unwrap().entries.iter().map(|e| e.0).collect()
+    }
+
+    /// Returns the raw XML of the entry with the given ID
+    pub fn entry(&self, id: u16) -> Option<String> {
+        self.state.lock().unwrap().entries.iter().find(|e| e.0 == id).map(|e| e.1.clone())
+    }
+
+    /// Creates or replaces an entry, as another client would
+    pub fn put_entry(&self, xml: &str) {
+        self.state.lock().unwrap().put_entry(xml);
+    }
+
+    /// Deletes an entry, as another client would
+    pub fn delete_entry(&self, id: u16) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.retain(|e| e.0 != id);
+        state.revision += 1;
+    }
+
+    /// Lets another client create or replace an entry right after the next
+    /// request to the given endpoint is handled
+    pub fn interleave(&self, endpoint: &str, xml: &str) {
+        self.state.lock().unwrap().interleaved.push((endpoint.to_string(), xml.to_string()));
+    }
+
+    /// Lets the next request to the given endpoint fail with the given status
+    pub fn fail_next(&self, endpoint: &str, status: u16) {
+        self.state.lock().unwrap().failures.push((endpoint.to_string(), status));
+    }
+
+    /// Handles the next request to the given endpoint, but answers it with a
+    /// gateway timeout, as if the answer got lost on its way back
+    pub fn lose_next(&self, endpoint: &str) {
+        self.state.lock().unwrap().lost.push(endpoint.to_string());
+    }
+
+    /// Lets the server reject change sets, like older servers do
+    pub fn without_changes(&self) {
+        self.state.lock().unwrap().changes = false;
+    }
+
+    /// Lets the server send no ETags and ignore preconditions, like older servers do
+    pub fn without_etags(&self) {
+        self.state.lock().unwrap().etags = false;
+    }
+
+    /// Returns the paths of all requests made so far
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl MockState {
+    fn put_entry(&mut self, xml: &str) {
+        for entry in parse_entries(xml) {
+            self.entries.retain(|e| e.0 != entry.0);
+            self.entries.push(entry);
+        }
+        self.revision += 1;
+    }
+}
+
+/// Returns a complete entry as the server would store it
+pub fn entry_xml(id: u16, title: &str, description: &str) -> String {
+    format!(
+        "<entry id=\"{}\"><name>{}</name><description>{}</description><tags></tags></entry>",
+        id, title, description
+    )
+}
+
+/// Splits a registry or a single entry into its entries
+fn parse_entries(xml: &str) -> Vec<(u16, String)> {
+    let mut entries: Vec<(u16, String)> = Vec::new();
+    let mut rest: &str = xml;
+    while let Some(start) = rest.find("<entry ") {
+        let Some(end) = rest[start..].find("</entry>") else {break};
+        let entry: &str = &rest[start..start + end + "</entry>".len()];
+        let id: Option<u16> = entry
+            .split("id=\"")
+            .nth(1)
+            .and_then(|e| e.split('"').next())
+            .and_then(|e| e.parse::<u16>().ok());
+        if let Some(id) = id {
+            entries.push((id, entry.to_string()));
+        }
+        rest = &rest[start + end..];
+    }
+    entries
+}
+
+/// Returns the content of the given section of a change set
+fn section<'a>(xml: &'a str, name: &str) -> &'a str {
+    let start: String = format!("<{}>", name);
+    let end: String = format!("</{}>", name);
+    xml
+        .split_once(&start)
+        .and_then(|e| e.1.split_once(&end))
+        .map(|e| e.0)
+        .unwrap_or("")
+}
+
+/// Returns the IDs of all deletions of a change set
+fn deletions(xml: &str) -> Vec<u16> {
+    xml
+        .split("<delete id=\"")
+        .skip(1)
+        .filter_map(|e| e.split('"').next())
+        .filter_map(|e| e.parse::<u16>().ok())
+        .collect()
+}
+
+fn respond(status: u16, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "text/xml")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn handle(state: Arc<Mutex<MockState>>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let path: String = req.uri().path().to_string();
+    let header = |name: &str| req
+        .headers()
+        .get(name)
+        .and_then(|e| e.to_str().ok())
+        .map(|e| e.to_string());
+    let (user, token, password) = (header("user"), header("token"), header("password"));
+    let if_match: Option<String> = header("if-match");
+    let body: String = hyper::body::to_bytes(req.into_body())
+        .await
+        .map(|e| String::from_utf8_lossy(&e).to_string())
+        .unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    state.requests.push(path.clone());
+
+    if let Some(pos) = state.failures.iter().position(|e| path.starts_with(&e.0)) {
+        let (_, status) = state.failures.remove(pos);
+        return Ok(respond(status, String::new()));
+    }
+
+    let authorized: bool = user.as_ref() == Some(&state.user)
+        && (token.as_ref() == Some(&state.secret) || password.as_ref() == Some(&state.secret));
+    if !authorized {
+        return Ok(respond(401, String::new()));
+    }
+
+    let etag: String = format!("\"{}\"", state.revision);
+    if state.etags && if_match.is_some() && if_match != Some(etag.clone()) {
+        return Ok(respond(412, String::new()));
+    }
+
+    let segments: Vec<&str> = path.split('/').filter(|e| !e.is_empty()).collect();
+    let id: Option<u16> = segments.get(2).and_then(|e| e.parse::<u16>().ok());
+    let known: bool = state.entries.iter().any(|e| Some(e.0) == id);
+
+    let response: Response<Body> = match segments.get(1).copied() {
+        Some("fetch") => {
+            let entries: String = state.entries.iter().map(|e| e.1.as_str()).collect();
+            let mut response: Response<Body> = respond(200, format!("<registry>{}</registry>", entries));
+            if state.etags {
+                response.headers_mut().insert("etag", etag.parse().unwrap());
+            }
+            response
+        },
+        Some("update") => {
+            state.entries = parse_entries(&body);
+            state.revision += 1;
+            respond(200, String::new())
+        },
+        Some("changes") if state.changes => {
+            let created: Vec<(u16, String)> = parse_entries(section(&body, "create"));
+            let modified: Vec<(u16, String)> = parse_entries(section(&body, "modify"));
+            let conflict: bool = created.iter().any(|n| state.entries.iter().any(|e| e.0 == n.0))
+                || modified.iter().any(|m| !state.entries.iter().any(|e| e.0 == m.0));
+            if conflict {
+                respond(409, String::new())
+            } else {
+                for entry in modified {
+                    let pos: usize = state.entries.iter().position(|e| e.0 == entry.0).unwrap();
+                    state.entries[pos] = entry;
+                }
+                state.entries.extend(created);
+                let deleted: Vec<u16> = deletions(&body);
+                state.entries.retain(|e| !deleted.contains(&e.0));
+                state.revision += 1;
+                respond(200, String::new())
+            }
+        },
+        Some("get_by_id") if known => {
+            let entry: String = state.entries.iter().find(|e| Some(e.0) == id).unwrap().1.clone();
+            respond(200, format!("<registry>{}</registry>", entry))
+        },
+        Some("add") => {
+            let new: Vec<(u16, String)> = parse_entries(&body);
+            if new.is_empty() || new.iter().any(|n| state.entries.iter().any(|e| e.0 == n.0)) {
+                respond(409, String::new())
+            } else {
+                state.entries.extend(new);
+                state.revision += 1;
+                respond(200, String::new())
+            }
+        },
+        Some("edit") if known => {
+            state.entries.retain(|e| Some(e.0) != id);
+            state.entries.extend(parse_entries(&body));
+            state.revision += 1;
+            respond(200, String::new())
+        },
+        Some("delete") if known => {
+            state.entries.retain(|e| Some(e.0) != id);
+            state.revision += 1;
+            respond(200, String::new())
+        },
+        _ => respond(404, String::new()),
+    };
+
+    if let Some(pos) = state.interleaved.iter().position(|e| path.starts_with(&e.0)) {
+        let (_, xml) = state.interleaved.remove(pos);
+        state.put_entry(&xml);
+    }
+    if let Some(pos) = state.lost.iter().position(|e| path.starts_with(e.as_str())) {
+        state.lost.remove(pos);
+        return Ok(respond(504, String::new()));
+    }
+    Ok(response)
+}
@@ -0,0 +1,42 @@
+mod backend;
+mod cache;
+mod events;
+mod journal;
+mod mock;
+mod recurrence;
+mod subtasks;
+mod sync;
+
+use chrono::{Local, TimeZone};
+use crate::data::data_types::{AppConfig, AppState, AuthMethod, Conflict, Resolution, SyncHandler};
+
+/// Keeps the local version of every conflicting field
+struct KeepLocal;
+
+impl SyncHandler for KeepLocal {
+    fn resolve(&mut self, _conflict: &Conflict) -> Resolution {
+        Resolution::Local
+    }
+}
+
+/// Returns a state working with the backend at the given address
+fn state_for(address: &str) -> AppState {
+    AppState::new(AppConfig::new(address.to_string(), String::new(), String::new(), AuthMethod::Token))
+}
+
+/// Returns a state working with a registry in memory
+fn memory_state() -> AppState {
+    state_for("memory://")
+}
+
+/// Returns the unix timestamp of the given local time
+fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> u32 {
+    Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap().timestamp() as u32
+}
+
+/// Returns the sorted titles of all elements of the state
+fn titles(state: &AppState) -> Vec<String> {
+    let mut titles: Vec<String> = state.get_elements().iter().map(|e| e.title()).collect();
+    titles.sort();
+    titles
+}
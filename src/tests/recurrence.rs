@@ -0,0 +1,158 @@
+use chrono::{Datelike, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc, Weekday};
+use crate::data::data_types::{AppElement, AppState, Recurrence};
+use super::{KeepLocal, local, memory_state};
+
+fn recurring(title: &str, due: u32, rule: &str) -> AppElement {
+    AppElement::new(None, title.to_string(), String::new(), Some(due), Vec::new())
+        .with_recurrence(Some(rule.parse().unwrap()))
+}
+
+fn open_element(state: &AppState) -> Option<&AppElement> {
+    state.get_elements().iter().find(|e| !e.is_completed())
+}
+
+#[test]
+fn rules_are_parsed() {
+    let rule: Recurrence = "rrule:freq=weekly;interval=2;byday=fr,mo;count=3".parse().unwrap();
+    assert_eq!(rule.to_string(), "every 2 weeks on MO, FR, 3 more time(s)");
+    assert_eq!("monthly".parse::<Recurrence>().unwrap().to_string(), "every month");
+    assert!("FREQ=HOURLY".parse::<Recurrence>().is_err());
+    assert!("FREQ=DAILY;INTERVAL=0".parse::<Recurrence>().is_err());
+    assert_eq!("FREQ=MONTHLY;BYMONTHDAY=31".parse::<Recurrence>().unwrap().to_string(), "every month on day 31");
+    assert!("FREQ=MONTHLY;BYMONTHDAY=32".parse::<Recurrence>().is_err());
+    assert!("FREQ=MONTHLY;BYDAY=MO".parse::<Recurrence>().is_err());
+    assert!("FREQ=WEEKLY;BYMONTHDAY=1".parse::<Recurrence>().is_err());
+    assert!("INTERVAL=2".parse::<Recurrence>().is_err());
+}
+
+#[test]
+fn completing_a_recurring_element_creates_the_next_occurrence() {
+    let mut state: AppState = memory_state();
+    let monday: u32 = (1..=7)
+        .map(|day| local(2099, 6, day, 9, 0))
+        .find(|e| Utc.timestamp_opt((*e).into(), 0).unwrap().with_timezone(&Local).weekday() == Weekday::Mon)
+        .unwrap();
+    state.push(Some(recurring("Review", monday, "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;COUNT=3")));
+
+    // Friday of the same week, then Monday two weeks later
+    let mut dues: Vec<u32> = Vec::new();
+    while let Some(element) = open_element(&state) {
+        dues.push(element.due().unwrap());
+        let handle: u32 = state.find_handle(&element.label()).unwrap();
+        assert!(state.set_completed(handle, true));
+    }
+
+    let days: Vec<i64> = dues.iter().map(|e| (i64::from(*e) - i64::from(monday)) / 86400).collect();
+    assert_eq!(days, vec![0, 4, 14]);
+    assert!(state.get_elements().iter().all(|e| e.recurrence().is_none()));
+}
+
+#[test]
+fn monthly_rules_keep_to_the_end_of_shorter_months() {
+    let mut state: AppState = memory_state();
+    state.push(Some(recurring("Invoice", local(2099, 1, 31, 10, 0), "FREQ=MONTHLY")));
+
+    // The day of the first occurrence is kept after February
+    let mut dues: Vec<u32> = Vec::new();
+    while dues.len() < 3 {
+        let element: &AppElement = open_element(&state).unwrap();
+        let handle: u32 = state.find_handle(&element.label()).unwrap();
+        assert!(state.set_completed(handle, true));
+        dues.push(open_element(&state).unwrap().due().unwrap());
+    }
+
+    assert_eq!(dues, vec![local(2099, 2, 28, 10, 0), local(2099, 3, 31, 10, 0), local(2099, 4, 30, 10, 0)]);
+    assert_eq!(open_element(&state).unwrap().recurrence().unwrap().to_string(), "every month on day 31");
+}
+
+#[test]
+fn next_occurrences_of_subtasks_keep_their_parent() {
+    let mut state: AppState = memory_state();
+    state.push(Some(AppElement::new(None, "Household".to_string(), String::new(), None, Vec::new())));
+    let parent: u32 = state.find_handle("new1").unwrap();
+    let subtask: AppElement = recurring("Water plants", local(2099, 1, 1, 10, 0), "FREQ=WEEKLY")
+        .with_parent(state.get_elements().iter().find(|e| e.handle() == parent));
+    state.push(Some(subtask));
+
+    assert!(state.set_completed(state.find_handle("new2").unwrap(), true));
+
+    let next: &AppElement = state.get_elements().iter().find(|e| e.title() == "Water plants" && !e.is_completed()).unwrap();
+    assert_eq!(next.parent_handle(), parent);
+    assert_eq!(state.subtasks(parent).len(), 2);
+}
+
+/// A time zone at UTC+1 that moves to UTC+2 at 2:00 on March 29 2099,
+/// the local times between 2:00 and 3:00 of that day don't exist
+#[derive(Clone)]
+struct SpringForward;
+
+impl SpringForward {
+    fn change() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2099, 3, 29).unwrap().and_hms_opt(2, 0, 0).unwrap()
+    }
+
+    fn offset(hours: i32) -> FixedOffset {
+        FixedOffset::east_opt(hours * 3600).unwrap()
+    }
+}
+
+impl TimeZone for SpringForward {
+    type Offset = FixedOffset;
+
+    fn from_offset(_offset: &FixedOffset) -> Self {
+        SpringForward
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+        self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+        match *local {
+            e if e < Self::change() => LocalResult::Single(Self::offset(1)),
+            e if e < Self::change() + TimeDelta::hours(1) => LocalResult::None,
+            _ => LocalResult::Single(Self::offset(2)),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+        self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match *utc < Self::change() - TimeDelta::hours(1) {
+            true => Self::offset(1),
+            false => Self::offset(2),
+        }
+    }
+}
+
+#[test]
+fn occurrences_in_the_gap_of_daylight_saving_time_move_past_it() {
+    let rule: Recurrence = "FREQ=DAILY".parse().unwrap();
+    let due = |day: u32, hour: u32| SpringForward.with_ymd_and_hms(2099, 3, day, hour, 30, 0).unwrap().timestamp() as u32;
+
+    let (next, rest) = rule.next_in(due(28, 2), &SpringForward).unwrap();
+    assert_eq!(next, due(29, 3));
+    assert!(rest.next_in(next, &SpringForward).is_some());
+}
+
+#[tokio::test]
+async fn passed_recurring_elements_roll_over_and_the_rule_is_synced() {
+    let mut state: AppState = memory_state();
+    let now: u32 = Utc::now().timestamp() as u32;
+    state.push(Some(recurring("Invoice", now - 10 * 86400, "FREQ=DAILY;INTERVAL=3")));
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    // The occurrences that passed already are skipped
+    assert!(state.is_synced());
+    let next: u32 = state.get_elements().iter().filter_map(|e| e.due()).max().unwrap();
+    assert!(next > now && next <= now + 3 * 86400 + 3600);
+
+    let remote: Vec<AppElement> = state.live_list().await.unwrap();
+    assert_eq!(remote.len(), 2);
+    let rules: Vec<Option<String>> = remote.iter().map(|e| e.recurrence().map(|r| r.to_string())).collect();
+    assert!(rules.contains(&Some("every 3 days".to_string())));
+    assert!(rules.contains(&None));
+}
@@ -0,0 +1,94 @@
+use crate::data::data_types::{AppElement, AppState};
+use super::mock::{entry_xml, MockServer};
+use super::{KeepLocal, memory_state};
+
+fn element(title: &str) -> AppElement {
+    AppElement::new(None, title.to_string(), String::new(), None, Vec::new())
+}
+
+/// Adds a subtask of the element with the given label
+fn push_subtask(state: &mut AppState, parent: &str, title: &str) {
+    let handle: u32 = state.find_handle(parent).unwrap();
+    let parent: Option<&AppElement> = state.get_elements().iter().find(|e| e.handle() == handle);
+    let subtask: AppElement = element(title).with_parent(parent);
+    state.push(Some(subtask));
+}
+
+fn id_of(state: &AppState, title: &str) -> u16 {
+    state.get_elements().iter().find(|e| e.title() == title).unwrap().id().unwrap()
+}
+
+#[tokio::test]
+async fn subtasks_of_new_elements_are_uploaded_with_the_id_of_their_parent() {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    let mut state: AppState = AppState::new(server.config());
+    state.push(Some(element("Move")));
+    push_subtask(&mut state, "new1", "Pack");
+    push_subtask(&mut state, "new2", "Tape");
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let (move_id, pack_id) = (id_of(&state, "Move"), id_of(&state, "Pack"));
+    assert!(server.entry(pack_id).unwrap().contains(&format!("<parent>{}</parent>", move_id)));
+    assert!(server.entry(id_of(&state, "Tape")).unwrap().contains(&format!("<parent>{}</parent>", pack_id)));
+    assert!(!server.entry(move_id).unwrap().contains("<parent>"));
+    assert!(state.is_synced());
+
+    // Nothing is left to upload for the new subtasks
+    assert!(state.direct_add(element("Clean")).await.unwrap());
+    assert!(state.is_synced());
+}
+
+#[tokio::test]
+async fn synced_subtasks_follow_a_new_parent() {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    server.set_registry(&format!("<registry>{}</registry>", entry_xml(1, "Pack", "")));
+    let mut state: AppState = AppState::new(server.config());
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    state.push(Some(element("Move")));
+    let (pack, parent) = (state.find_handle("1").unwrap(), state.find_handle("new2").unwrap());
+    assert!(state.set_parent(pack, Some(parent)));
+    state.unsynced();
+    state.sync(&mut KeepLocal).await.unwrap();
+
+    let move_id: u16 = id_of(&state, "Move");
+    assert!(server.entry(1).unwrap().contains(&format!("<parent>{}</parent>", move_id)));
+    assert_eq!(state.subtasks(state.find_handle(&move_id.to_string()).unwrap()), vec![pack]);
+    assert!(state.is_synced());
+}
+
+#[test]
+fn parents_roll_up_the_progress_of_their_subtasks() {
+    let mut state: AppState = memory_state();
+    state.push(Some(element("Move")));
+    push_subtask(&mut state, "new1", "Pack");
+    push_subtask(&mut state, "new2", "Tape");
+    push_subtask(&mut state, "new1", "Clean");
+
+    let root: u32 = state.find_handle("new1").unwrap();
+    assert_eq!(state.subtask_progress(root), Some((0, 3)));
+    assert!(state.set_completed(state.find_handle("new3").unwrap(), true));
+    assert_eq!(state.subtask_progress(root), Some((1, 3)));
+    assert_eq!(state.subtask_progress(state.find_handle("new2").unwrap()), Some((1, 1)));
+    assert_eq!(state.subtask_progress(state.find_handle("new4").unwrap()), None);
+
+    assert!(state.remove(state.find_handle("new4").unwrap()));
+    assert_eq!(state.subtask_progress(root), Some((1, 2)));
+}
+
+#[test]
+fn parents_can_not_become_subtasks_of_their_subtasks() {
+    let mut state: AppState = memory_state();
+    state.push(Some(element("Move")));
+    push_subtask(&mut state, "new1", "Pack");
+    push_subtask(&mut state, "new2", "Tape");
+
+    let (root, leaf) = (state.find_handle("new1").unwrap(), state.find_handle("new3").unwrap());
+    assert!(!state.set_parent(root, Some(leaf)));
+    assert!(!state.set_parent(root, Some(root)));
+    assert!(!state.set_parent(root, Some(42)));
+    assert!(state.set_parent(leaf, None));
+    assert!(state.set_parent(root, Some(leaf)));
+    assert_eq!(state.subtasks(leaf), vec![root, state.find_handle("new2").unwrap()]);
+}
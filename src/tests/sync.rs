@@ -0,0 +1,655 @@
+use crate::data::data_types::{AppConfig, AppElement, AppState, AuthMethod, Conflict, MergeField, Priority, Resolution, SyncHandler, SyncPlan};
+use crate::error::AppError;
+use super::mock::{MockServer, entry_xml};
+use super::titles;
+
+/// Resolves every conflict the same way and remembers the conflicts
+struct RecordingHandler {
+    resolution: Resolution,
+    conflicts: Vec<(u16, MergeField)>,
+    /// Whether the reviewed changes may be sent
+    approve: bool,
+    plans: Vec<String>,
+}
+
+impl RecordingHandler {
+    fn new(resolution: Resolution) -> Self {
+        Self {
+            resolution,
+            conflicts: Vec::new(),
+            approve: true,
+            plans: Vec::new(),
+        }
+    }
+}
+
+impl SyncHandler for RecordingHandler {
+    fn resolve(&mut self, conflict: &Conflict) -> Resolution {
+        self.conflicts.push((conflict.id, conflict.field));
+        self.resolution
+    }
+
+    fn review(&mut self, plan: &SyncPlan) -> bool {
+        self.plans.push(plan.to_string());
+        self.approve
+    }
+}
+
+/// Starts a server with two entries and a state that is synced with it
+async fn setup() -> (MockServer, AppState) {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    server.set_registry(&format!(
+        "<registry>{}{}</registry>",
+        entry_xml(1, "First", "one"),
+        entry_xml(2, "Second", "two"),
+    ));
+    let mut state: AppState = AppState::new(server.config());
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+    (server, state)
+}
+
+#[tokio::test]
+async fn sync_fetches_the_registry() {
+    let (_server, state) = setup().await;
+
+    assert_eq!(titles(&state), vec!["First", "Second"]);
+    assert!(state.is_synced());
+}
+
+#[tokio::test]
+async fn added_element_is_uploaded_with_a_new_id() {
+    let (server, mut state) = setup().await;
+
+    state.push(Some(AppElement::new(None, "Third".to_string(), "three".to_string(), Some(100), vec!["tag".to_string()])));
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    let third: &AppElement = state.get_elements().iter().find(|e| e.title() == "Third").unwrap();
+    let id: u16 = third.id().unwrap();
+    assert!(server.ids().contains(&id));
+    assert!(server.entry(id).unwrap().contains("<name>Third</name>"));
+    assert!(server.entry(id).unwrap().contains("<tag>tag</tag>"));
+    assert!(state.is_synced());
+}
+
+#[tokio::test]
+async fn edited_element_is_uploaded() {
+    let (server, mut state) = setup().await;
+
+    state.get_element_by_id(1).unwrap().modify("Changed".to_string(), "one".to_string(), Some(5), Vec::new());
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert!(server.entry(1).unwrap().contains("<name>Changed</name>"));
+    assert!(server.entry(1).unwrap().contains("<due>5</due>"));
+    assert!(server.entry(2).unwrap().contains("<name>Second</name>"));
+}
+
+#[tokio::test]
+async fn completion_is_synced_both_ways() {
+    let (server, mut state) = setup().await;
+
+    assert!(state.set_completed(state.find_handle("1").unwrap(), true));
+    assert!(!state.set_completed(state.find_handle("1").unwrap(), true));
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert!(state.get_element_by_id(1).unwrap().is_completed());
+    assert!(server.entry(1).unwrap().contains("<completed>"));
+
+    // Editing a completed element keeps a single completion timestamp
+    state.get_element_by_id(1).unwrap().modify("Done".to_string(), "one".to_string(), None, Vec::new());
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+    assert_eq!(server.entry(1).unwrap().matches("<completed>").count(), 1);
+
+    server.put_entry(&entry_xml(1, "Done", "one"));
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+    assert!(!state.get_element_by_id(1).unwrap().is_completed());
+}
+
+#[tokio::test]
+async fn priority_is_synced_and_sortable() {
+    let (server, mut state) = setup().await;
+
+    state.push(Some(AppElement::new(None, "Third".to_string(), String::new(), None, Vec::new()).with_priority(Priority::High)));
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    let id: u16 = state.get_elements().iter().find(|e| e.title() == "Third").unwrap().id().unwrap();
+    assert!(server.entry(id).unwrap().contains("<priority>high</priority>"));
+    assert!(!server.entry(1).unwrap().contains("<priority>"));
+
+    server.put_entry(&entry_xml(2, "Second", "two").replace("<tags>", "<priority>medium</priority><tags>"));
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+    assert_eq!(state.get_element_by_id(2).unwrap().priority(), Priority::Medium);
+
+    state.sort_by_priority();
+    let titles: Vec<String> = state.get_elements().iter().map(|e| e.title()).collect();
+    assert_eq!(titles, vec!["Third", "Second", "First"]);
+}
+
+#[tokio::test]
+async fn removed_element_is_deleted_on_the_server() {
+    let (server, mut state) = setup().await;
+
+    assert!(state.remove(state.find_handle("2").unwrap()));
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert_eq!(server.ids(), vec![1]);
+    assert_eq!(titles(&state), vec!["First"]);
+}
+
+#[tokio::test]
+async fn concurrent_changes_of_different_fields_are_merged() {
+    let (server, mut state) = setup().await;
+
+    server.put_entry(&entry_xml(1, "Renamed on the server", "one"));
+    state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+    state.unsynced();
+
+    let mut handler: RecordingHandler = RecordingHandler::new(Resolution::Local);
+    state.sync(&mut handler).await.unwrap();
+
+    assert!(handler.conflicts.is_empty());
+    let entry: String = server.entry(1).unwrap();
+    assert!(entry.contains("<name>Renamed on the server</name>"));
+    assert!(entry.contains("<description>changed locally</description>"));
+    assert_eq!(state.get_element_by_id(1).unwrap().title(), "Renamed on the server");
+}
+
+#[tokio::test]
+async fn concurrent_changes_of_the_same_field_are_resolved_by_the_handler() {
+    for (resolution, expected) in [(Resolution::Local, "Local"), (Resolution::Remote, "Remote")] {
+        let (server, mut state) = setup().await;
+
+        server.put_entry(&entry_xml(1, "Remote", "one"));
+        state.get_element_by_id(1).unwrap().modify("Local".to_string(), "one".to_string(), None, Vec::new());
+        state.unsynced();
+
+        let mut handler: RecordingHandler = RecordingHandler::new(resolution);
+        state.sync(&mut handler).await.unwrap();
+
+        assert_eq!(handler.conflicts, vec![(1, MergeField::Title)]);
+        assert!(server.entry(1).unwrap().contains(&format!("<name>{}</name>", expected)));
+        assert_eq!(state.get_element_by_id(1).unwrap().title(), expected);
+    }
+}
+
+#[tokio::test]
+async fn remote_edits_and_deletions_are_pulled() {
+    let (server, mut state) = setup().await;
+
+    server.put_entry(&entry_xml(2, "Second edited", "two"));
+    server.delete_entry(1);
+    server.put_entry(&entry_xml(3, "Third", "three"));
+
+    let report = state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert_eq!(report.added, vec![3]);
+    assert_eq!(report.updated, vec![2]);
+    assert_eq!(report.deleted, vec![1]);
+    assert_eq!(titles(&state), vec!["Second edited", "Third"]);
+}
+
+#[tokio::test]
+async fn locally_edited_element_deleted_on_the_server_is_created_again() {
+    let (server, mut state) = setup().await;
+
+    server.delete_entry(1);
+    state.get_element_by_id(1).unwrap().modify("Still needed".to_string(), "one".to_string(), None, Vec::new());
+    state.unsynced();
+
+    let report = state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert_eq!(report.revived, vec![1]);
+    assert_eq!(server.ids().len(), 2);
+    assert_eq!(titles(&state), vec!["Second", "Still needed"]);
+}
+
+#[tokio::test]
+async fn new_elements_are_uploaded_with_a_uuid() {
+    let (server, mut state) = setup().await;
+
+    state.push(Some(AppElement::new(None, "Third".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    let id: u16 = state.get_elements().iter().find(|e| e.title() == "Third").unwrap().id().unwrap();
+    assert!(server.entry(id).unwrap().contains(" uuid=\""));
+    assert!(!server.entry(1).unwrap().contains(" uuid=\""));
+}
+
+#[tokio::test]
+async fn elements_whose_upload_answer_got_lost_are_not_created_twice() {
+    for changes in [true, false] {
+        let (server, mut state) = setup().await;
+        let mut config: AppConfig = server.config();
+        config.network.backoff = 1;
+        state.set_config(config);
+
+        state.push(Some(AppElement::new(None, "Third".to_string(), "three".to_string(), None, Vec::new())));
+        state.unsynced();
+        if changes {
+            // Change sets are not sent again, the sync fails
+            server.lose_next("/xml/changes");
+            let result = state.sync(&mut RecordingHandler::new(Resolution::Local)).await;
+            assert!(matches!(result, Err(AppError::Status(504))));
+            assert_eq!(server.ids().len(), 3);
+        } else {
+            // The update is sent again and rejected, as the registry changed
+            // with the first one, then the sync starts over
+            server.without_changes();
+            server.lose_next("/xml/update");
+        }
+
+        state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+        assert_eq!(server.ids().len(), 3);
+        assert_eq!(titles(&state), vec!["First", "Second", "Third"]);
+        let third: &AppElement = state.get_elements().iter().find(|e| e.title() == "Third").unwrap();
+        assert!(server.ids().contains(&third.id().unwrap()));
+        assert!(state.is_synced());
+    }
+}
+
+#[tokio::test]
+async fn unsynced_elements_can_be_selected_edited_and_removed() {
+    let (server, mut state) = setup().await;
+
+    state.push(Some(AppElement::new(None, "Third".to_string(), String::new(), None, Vec::new())));
+    state.push(Some(AppElement::new(None, "Fourth".to_string(), String::new(), None, Vec::new())));
+    let labels: Vec<String> = state.get_labels(|_| true).into_iter().map(|e| e.1).collect();
+    assert_eq!(labels, vec!["1", "2", "new3", "new4"]);
+
+    let elements: &Vec<AppElement> = state.get_elements();
+    assert!(elements[2] == elements[2].clone() && elements[2] != elements[3]);
+
+    let third: u32 = state.find_handle("new3").unwrap();
+    state.get_element(third).unwrap().modify("Third edited".to_string(), String::new(), None, Vec::new());
+    assert!(state.remove(state.find_handle("new4").unwrap()));
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    // The handle stays the same when the element gets its ID
+    let id: u16 = state.get_element(third).unwrap().id().unwrap();
+    assert_eq!(state.find_handle(&id.to_string()), Some(third));
+    assert_eq!(state.find_handle("new3"), Some(third));
+    assert_eq!(server.ids().len(), 3);
+    assert!(server.entry(id).unwrap().contains("<name>Third edited</name>"));
+}
+
+#[tokio::test]
+async fn labels_of_removed_elements_are_not_reused() {
+    let (server, mut state) = setup().await;
+
+    state.push(Some(AppElement::new(None, "Third".to_string(), String::new(), None, Vec::new())));
+    assert!(state.remove(state.find_handle("new3").unwrap()));
+
+    // Neither in the same run nor after the cache was written and read again
+    let mut next: AppState = AppState::new(server.config());
+    next.load_cache(state.to_cache());
+    for state in [&mut state, &mut next] {
+        state.push(Some(AppElement::new(None, "Fourth".to_string(), String::new(), None, Vec::new())));
+        assert_eq!(state.find_handle("new3"), None);
+        assert!(state.find_handle("new4").is_some());
+    }
+}
+
+#[tokio::test]
+async fn entries_of_other_clients_with_a_reused_id_are_kept() {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    let with_uuid = |id: u16, uuid: &str, title: &str| entry_xml(id, title, "").replacen('>', &format!(" uuid=\"{}\">", uuid), 1);
+    server.set_registry(&format!("<registry>{}{}</registry>", with_uuid(1, "a", "First"), with_uuid(2, "b", "Second")));
+    let mut state: AppState = AppState::new(server.config());
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    // Another client replaced both entries with its own ones under the same IDs
+    server.set_registry(&format!("<registry>{}{}</registry>", with_uuid(1, "c", "Other first"), with_uuid(2, "d", "Other second")));
+    state.get_element_by_id(1).unwrap().modify("Edited".to_string(), String::new(), None, Vec::new());
+    assert!(state.remove(state.find_handle("2").unwrap()));
+    state.unsynced();
+
+    let report = state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert_eq!(report.revived, vec![1]);
+    assert_eq!(server.ids().len(), 3);
+    assert!(server.entry(1).unwrap().contains("<name>Other first</name>"));
+    assert!(server.entry(2).unwrap().contains("<name>Other second</name>"));
+    assert_eq!(titles(&state), vec!["Edited", "Other first", "Other second"]);
+}
+
+#[tokio::test]
+async fn rejected_upload_keeps_the_local_changes() {
+    let (server, mut state) = setup().await;
+
+    state.remove(state.find_handle("2").unwrap());
+    state.unsynced();
+    server.fail_next("/xml/changes", 500);
+
+    let result = state.sync(&mut RecordingHandler::new(Resolution::Local)).await;
+
+    assert!(matches!(result, Err(AppError::Status(500))));
+    assert!(!state.is_synced());
+    assert_eq!(server.ids(), vec![1, 2]);
+
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+    assert_eq!(server.ids(), vec![1]);
+}
+
+#[tokio::test]
+async fn failed_sync_without_local_changes_stays_synced() {
+    let (server, mut state) = setup().await;
+
+    server.fail_next("/xml/fetch", 500);
+    let result = state.sync(&mut RecordingHandler::new(Resolution::Local)).await;
+
+    assert!(matches!(result, Err(AppError::Status(500))));
+    assert!(state.is_synced());
+}
+
+#[tokio::test]
+async fn wrong_credentials_are_reported() {
+    let server: MockServer = MockServer::start("alice", "secret").await;
+    let config: AppConfig = server.config();
+    let config: AppConfig = AppConfig::new(config.server_address, config.username, "wrong".to_string(), AuthMethod::Password);
+    let mut state: AppState = AppState::new(config);
+
+    let result = state.sync(&mut RecordingHandler::new(Resolution::Local)).await;
+
+    assert!(matches!(result, Err(AppError::Auth(401))));
+}
+
+#[tokio::test]
+async fn direct_changes_use_targeted_requests() {
+    let (server, mut state) = setup().await;
+
+    assert!(state.direct_add(AppElement::new(None, "Direct".to_string(), String::new(), None, Vec::new())).await.unwrap());
+    let id: u16 = state.get_elements().iter().find(|e| e.title() == "Direct").unwrap().id().unwrap();
+    assert!(server.entry(id).unwrap().contains("<name>Direct</name>"));
+
+    state.get_element_by_id(1).unwrap().modify("Direct edit".to_string(), "one".to_string(), None, Vec::new());
+    assert!(state.direct_edit(1).await.unwrap());
+    assert!(server.entry(1).unwrap().contains("<name>Direct edit</name>"));
+
+    state.remove(state.find_handle("2").unwrap());
+    assert!(state.direct_remove(2).await.unwrap());
+    assert!(server.entry(2).is_none());
+
+    assert!(state.is_synced());
+    assert!(!server.requests().iter().any(|e| e == "/xml/update"));
+}
+
+#[tokio::test]
+async fn direct_additions_avoid_ids_taken_on_the_server_since_the_sync() {
+    let (server, mut state) = setup().await;
+    // Other clients took every ID but two
+    let entries: String = (1..u16::MAX - 1).map(|e| entry_xml(e, "Taken", "")).collect();
+    server.set_registry(&format!("<registry>{}</registry>", entries));
+
+    let mut element: AppElement = AppElement::new(None, "Live".to_string(), String::new(), None, Vec::new());
+    assert!(state.live_add(&mut element).await.unwrap().is_some());
+    assert!(state.direct_add(AppElement::new(None, "Direct".to_string(), String::new(), None, Vec::new())).await.unwrap());
+
+    let mut ids: Vec<u16> = server.ids().into_iter().filter(|e| *e >= u16::MAX - 1).collect();
+    ids.sort();
+    assert_eq!(ids, vec![u16::MAX - 1, u16::MAX]);
+}
+
+#[tokio::test]
+async fn direct_removals_are_kept_if_the_server_has_no_delete_route() {
+    let (server, mut state) = setup().await;
+
+    state.remove(state.find_handle("2").unwrap());
+    server.fail_next("/xml/delete", 404);
+    assert!(!state.direct_remove(2).await.unwrap());
+    assert!(server.entry(2).is_some());
+    assert!(!state.is_synced());
+
+    // The removal is still pending and sent with the next sync
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+    assert_eq!(server.ids(), vec![1]);
+    assert!(state.get_element_by_id(2).is_none());
+
+    // An element another client deleted already counts as deleted
+    state.remove(state.find_handle("1").unwrap());
+    server.delete_entry(1);
+    assert!(state.direct_remove(1).await.unwrap());
+    assert!(state.is_synced());
+}
+
+#[tokio::test]
+async fn refreshing_takes_over_remote_changes_without_uploading() {
+    let (server, mut state) = setup().await;
+
+    state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+    state.unsynced();
+    server.put_entry(&entry_xml(1, "Renamed meanwhile", "one"));
+    server.put_entry(&entry_xml(2, "Second", "changed remotely"));
+    state.refresh().await.unwrap();
+
+    assert_eq!(state.get_element_by_id(2).unwrap().description(), "changed remotely");
+    assert_eq!(state.get_element_by_id(1).unwrap().title(), "First");
+    assert!(server.entry(1).unwrap().contains("<description>one</description>"));
+    assert!(!state.is_synced());
+
+    // The local change is still merged with the next sync
+    state.sync(&mut RecordingHandler::new(Resolution::Remote)).await.unwrap();
+    let entry: String = server.entry(1).unwrap();
+    assert!(entry.contains("<name>Renamed meanwhile</name>"));
+    assert!(entry.contains("<description>changed locally</description>"));
+}
+
+#[tokio::test]
+async fn rejected_plan_sends_nothing() {
+    let (server, mut state) = setup().await;
+
+    state.get_element_by_id(1).unwrap().modify("Changed".to_string(), "one".to_string(), None, Vec::new());
+    state.remove(state.find_handle("2").unwrap());
+    state.unsynced();
+
+    let mut handler: RecordingHandler = RecordingHandler::new(Resolution::Local);
+    handler.approve = false;
+    let result = state.sync(&mut handler).await;
+
+    assert!(matches!(result, Err(AppError::Aborted)));
+    assert_eq!(handler.plans.len(), 1);
+    assert!(handler.plans[0].contains("Changed"));
+    assert!(handler.plans[0].contains("Second"));
+    assert!(!state.is_synced());
+    assert_eq!(server.ids(), vec![1, 2]);
+    assert!(server.entry(1).unwrap().contains("<name>First</name>"));
+    assert!(!server.requests().iter().any(|e| e == "/xml/update"));
+}
+
+#[tokio::test]
+async fn rejected_empty_plan_leaves_the_state_as_it_was() {
+    let (server, mut state) = setup().await;
+    server.put_entry(&entry_xml(1, "Renamed remotely", "one"));
+
+    let mut handler: RecordingHandler = RecordingHandler::new(Resolution::Local);
+    handler.approve = false;
+    let result = state.sync(&mut handler).await;
+
+    assert!(matches!(result, Err(AppError::Aborted)));
+    assert_eq!(handler.plans, vec!["Nothing to upload"]);
+    assert_eq!(state.get_element_by_id(1).unwrap().title(), "First");
+}
+
+#[tokio::test]
+async fn only_changes_are_uploaded() {
+    let (server, mut state) = setup().await;
+
+    state.get_element_by_id(1).unwrap().modify("Changed".to_string(), "one".to_string(), None, Vec::new());
+    state.remove(state.find_handle("2").unwrap());
+    state.push(Some(AppElement::new(None, "Third".to_string(), String::new(), None, Vec::new())));
+    state.unsynced();
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert!(server.requests().iter().any(|e| e == "/xml/changes"));
+    assert!(!server.requests().iter().any(|e| e == "/xml/update"));
+    assert_eq!(server.ids().len(), 2);
+    assert!(server.entry(1).unwrap().contains("<name>Changed</name>"));
+    assert!(server.entry(2).is_none());
+    assert_eq!(titles(&state), vec!["Changed", "Third"]);
+}
+
+#[tokio::test]
+async fn whole_registry_is_uploaded_if_change_sets_are_not_supported() {
+    let (server, mut state) = setup().await;
+    server.without_changes();
+
+    for title in ["Changed", "Changed again"] {
+        state.get_element_by_id(1).unwrap().modify(title.to_string(), "one".to_string(), None, Vec::new());
+        state.unsynced();
+        state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+        assert!(server.entry(1).unwrap().contains(&format!("<name>{}</name>", title)));
+    }
+
+    let requests: Vec<String> = server.requests();
+    assert_eq!(requests.iter().filter(|e| *e == "/xml/changes").count(), 1);
+    assert_eq!(requests.iter().filter(|e| *e == "/xml/update").count(), 2);
+}
+
+#[tokio::test]
+async fn changes_made_during_the_sync_are_merged_again() {
+    for etags in [true, false] {
+        let (server, mut state) = setup().await;
+        if !etags {
+            server.without_etags();
+        }
+
+        state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+        state.unsynced();
+        server.interleave("/xml/fetch", &entry_xml(1, "Renamed meanwhile", "one"));
+
+        state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+        let entry: String = server.entry(1).unwrap();
+        assert!(entry.contains("<name>Renamed meanwhile</name>"));
+        assert!(entry.contains("<description>changed locally</description>"));
+        assert!(state.is_synced());
+        assert_eq!(server.requests().iter().filter(|e| *e == "/xml/changes").count(), if etags {2} else {1});
+    }
+}
+
+#[tokio::test]
+async fn concurrent_changes_of_any_field_are_detected_without_etags() {
+    let changes: [(&str, &str); 6] = [
+        ("completion", "<completed>4070908800</completed>"),
+        ("priority", "<priority>high</priority>"),
+        ("recurrence", "<recurrence>FREQ=WEEKLY</recurrence>"),
+        ("parent", "<parent>2</parent>"),
+        ("event", "<start>4070908800</start><end>4070912400</end>"),
+        ("all-day flag", "<start>4070908800</start><allday>true</allday>"),
+    ];
+    for (field, change) in changes {
+        let (server, mut state) = setup().await;
+        server.without_etags();
+
+        state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+        state.unsynced();
+        server.interleave("/xml/fetch", &entry_xml(1, "First", "one").replace("<tags>", &format!("{}<tags>", change)));
+        state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+        let entry: String = server.entry(1).unwrap();
+        assert!(entry.contains(change), "the concurrent change of the {} was lost: {}", field, entry);
+        assert!(entry.contains("<description>changed locally</description>"));
+    }
+}
+
+#[tokio::test]
+async fn concurrent_uuid_of_an_entry_is_detected_without_etags() {
+    let (server, mut state) = setup().await;
+    server.without_etags();
+
+    state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+    state.unsynced();
+    server.interleave("/xml/fetch", &entry_xml(1, "First", "one").replace("id=\"1\"", "id=\"1\" uuid=\"other\""));
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert!(server.entry(1).unwrap().contains("uuid=\"other\""));
+}
+
+#[tokio::test]
+async fn writes_between_the_hash_check_and_the_upload_are_kept() {
+    let (server, mut state) = setup().await;
+    server.without_etags();
+
+    state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+    state.unsynced();
+    // The first fetch is the one of the sync, the write follows the fetch
+    // that checks the hash right before the upload
+    server.interleave("/xml/fetch", &entry_xml(2, "Second", "two"));
+    server.interleave("/xml/fetch", &entry_xml(3, "Written meanwhile", "three"));
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert!(server.entry(1).unwrap().contains("<description>changed locally</description>"));
+    assert!(server.entry(3).is_some());
+}
+
+#[tokio::test]
+async fn whole_registry_is_not_uploaded_without_an_etag() {
+    let (server, mut state) = setup().await;
+    server.without_etags();
+    server.without_changes();
+
+    state.get_element_by_id(1).unwrap().modify("First".to_string(), "changed locally".to_string(), None, Vec::new());
+    state.unsynced();
+    let result = state.sync(&mut RecordingHandler::new(Resolution::Local)).await;
+
+    assert!(matches!(result, Err(AppError::UnprotectedUpload)));
+    assert!(!server.requests().iter().any(|e| e == "/xml/update"));
+    assert!(server.entry(1).unwrap().contains("<description>one</description>"));
+    assert!(!state.is_synced());
+}
+
+#[tokio::test]
+async fn temporary_server_errors_are_retried() {
+    let (server, mut state) = setup().await;
+    let mut config: AppConfig = server.config();
+    config.network.backoff = 1;
+    state.set_config(config);
+
+    server.fail_next("/xml/fetch", 503);
+    server.fail_next("/xml/fetch", 502);
+    state.sync(&mut RecordingHandler::new(Resolution::Local)).await.unwrap();
+
+    assert!(state.is_synced());
+    assert_eq!(server.requests().iter().filter(|e| *e == "/xml/fetch").count(), 4);
+}
+
+#[tokio::test]
+async fn change_sets_are_not_sent_twice() {
+    let (server, mut state) = setup().await;
+    let mut config: AppConfig = server.config();
+    config.network.backoff = 1;
+    state.set_config(config);
+
+    state.remove(state.find_handle("2").unwrap());
+    state.unsynced();
+    server.fail_next("/xml/changes", 503);
+    let result = state.sync(&mut RecordingHandler::new(Resolution::Local)).await;
+
+    assert!(matches!(result, Err(AppError::Status(503))));
+    assert_eq!(server.requests().iter().filter(|e| *e == "/xml/changes").count(), 1);
+    assert_eq!(server.ids(), vec![1, 2]);
+}
+
+#[tokio::test]
+async fn background_sync_leaves_conflicts_to_the_user() {
+    let (server, mut state) = setup().await;
+
+    server.put_entry(&entry_xml(1, "Remote", "one"));
+    state.get_element_by_id(1).unwrap().modify("Local".to_string(), "one".to_string(), None, Vec::new());
+    state.unsynced();
+
+    let mut handler = crate::BackgroundSyncHandler::default();
+    let result = state.sync(&mut handler).await;
+
+    assert!(matches!(result, Err(AppError::Aborted)));
+    assert_eq!(handler.conflicts, 1);
+    assert!(server.entry(1).unwrap().contains("<name>Remote</name>"));
+    assert_eq!(state.get_element_by_id(1).unwrap().title(), "Local");
+    assert!(!state.is_synced());
+}